@@ -9,9 +9,15 @@ fn main() {
     let host = "127.0.0.1";
     let port = "7878";
 
+    // the path /status/{code} makes the server respond with the given status, e.g. /status/500
     let server = Server::new(|request, mut response| {
         let body = str::from_utf8(request.body()).unwrap();
         println!("{}",body);
+        let status = request.uri().path()
+            .trim_start_matches("/status/")
+            .parse::<u16>()
+            .unwrap_or(200);
+        response.status(status);
         Ok(response.body(body.as_bytes().to_vec())?)
     });
 
@@ -1,17 +1,35 @@
+//! ### Errors
+//! The error returned by the parsers, the generators and the senders.
+
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
 use crate::error::GenErrorType::{Common, Parser, Generator, Sender};
 
 /// The general error wrapper denoting the errors occurring in the generator.
-/// It consists of the string reason and the type spotting where the error occurred.
+/// It consists of the string reason, the type spotting where the error occurred and the stable code.
+/// The errors of the templates can also have the path of the field, the name of the generator
+/// and the span of the generator in the expression.
+/// The underlying error (e.g. `io::Error`) is kept as the source.
 #[derive(Debug)]
 pub struct GenError {
     reason: String,
     tpe: GenErrorType,
+    code: ErrorCode,
+    place: Option<Box<Place>>,
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+/// The place of the error in the template.
+#[derive(Debug, Default)]
+struct Place {
+    path: Option<String>,
+    generator: Option<String>,
+    span: Option<(String, Range<usize>)>,
 }
 
 /// The type of the error depicting the place where the error occurred.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GenErrorType {
     /// the parser place
     Parser,
@@ -23,24 +41,129 @@ pub enum GenErrorType {
     Common,
 }
 
+/// The stable machine-readable code of the error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCode {
+    /// the template is not a valid json.
+    InvalidJson,
+    /// the generator expression can not be parsed.
+    Syntax,
+    /// the generator function is unknown.
+    UnknownGenerator,
+    /// the arguments of the generator function are invalid.
+    InvalidArguments,
+    /// the generator can not follow the previous generators in the chain.
+    InvalidChain,
+    /// the reference to another field is unknown or cyclic.
+    InvalidReference,
+    /// the generator has failed to generate the value.
+    Generation,
+    /// the sender has failed to send the json.
+    Sending,
+    /// the input or the output has failed.
+    Io,
+    /// the place is undefined.
+    Other,
+}
+
+impl ErrorCode {
+    /// the code like `unknown-generator`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidJson => "invalid-json",
+            ErrorCode::Syntax => "syntax",
+            ErrorCode::UnknownGenerator => "unknown-generator",
+            ErrorCode::InvalidArguments => "invalid-arguments",
+            ErrorCode::InvalidChain => "invalid-chain",
+            ErrorCode::InvalidReference => "invalid-reference",
+            ErrorCode::Generation => "generation",
+            ErrorCode::Sending => "sending",
+            ErrorCode::Io => "io",
+            ErrorCode::Other => "other",
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl GenError {
-    /// create a new error with the type Common
-    pub fn new_with(reason: &str) -> Self {
-        GenError { reason: reason.to_string(), tpe: Common }
+    fn new(reason: &str, tpe: GenErrorType, code: ErrorCode) -> Self {
+        GenError { reason: reason.to_string(), tpe, code, place: None, source: None }
     }
+    /// create a new error with the type Common
+    pub fn new_with(reason: &str) -> Self { GenError::new(reason, Common, ErrorCode::Other) }
     /// create a new error with the type Parser
-    pub fn new_with_in_parser(reason: &str) -> Self { GenError { reason: reason.to_string(), tpe: Parser } }
+    pub fn new_with_in_parser(reason: &str) -> Self { GenError::new(reason, Parser, ErrorCode::Syntax) }
     /// create a new error with the type Sender
-    pub fn new_with_in_sender(reason: &str) -> Self { GenError { reason: reason.to_string(), tpe: Sender } }
+    pub fn new_with_in_sender(reason: &str) -> Self { GenError::new(reason, Sender, ErrorCode::Sending) }
     /// create a new error with the type Generator
-    pub fn new_with_in_generator(reason: &str) -> Self { GenError { reason: reason.to_string(), tpe: Generator } }
+    pub fn new_with_in_generator(reason: &str) -> Self { GenError::new(reason, Generator, ErrorCode::Generation) }
+
+    /// sets the code of the error
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+    /// sets the name of the generator function which has failed
+    pub fn with_generator(mut self, generator: &str) -> Self {
+        self.place().generator = Some(generator.to_string());
+        self
+    }
+    /// sets the span of the failed generator in bytes from the start of the expression
+    pub fn with_span(mut self, expression: &str, span: Range<usize>) -> Self {
+        self.place().span = Some((expression.to_string(), span));
+        self
+    }
+    /// sets the underlying error
+    pub fn with_source<E: Error + Send + Sync + 'static>(mut self, source: E) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+    /// places the error in the field of the template.
+    /// The path of the field is prepended to the path of the nested field the error already has.
+    pub fn in_field(mut self, path: &str) -> Self {
+        let place = self.place();
+        place.path = match place.path.take() {
+            Some(nested) if !path.is_empty() => Some(format!("{}.{}", path, nested)),
+            Some(nested) => Some(nested),
+            None => Some(path.to_string()),
+        };
+        self
+    }
+
+    fn place(&mut self) -> &mut Place {
+        self.place.get_or_insert_with(Default::default)
+    }
+
+    /// the reason of the error
+    pub fn reason(&self) -> &str { self.reason.as_str() }
+    /// the place where the error occurred
+    pub fn tpe(&self) -> GenErrorType { self.tpe }
+    /// the code of the error
+    pub fn code(&self) -> ErrorCode { self.code }
+    /// the path of the field in the template like `user.tags.0`
+    pub fn path(&self) -> Option<&str> { self.place.as_ref().and_then(|p| p.path.as_deref()) }
+    /// the name of the generator function
+    pub fn generator(&self) -> Option<&str> { self.place.as_ref().and_then(|p| p.generator.as_deref()) }
+    /// the generator expression
+    pub fn expression(&self) -> Option<&str> { self.place.as_ref().and_then(|p| p.span.as_ref()).map(|(e, _)| e.as_str()) }
+    /// the span of the failed generator in bytes from the start of the expression
+    pub fn span(&self) -> Option<Range<usize>> { self.place.as_ref().and_then(|p| p.span.as_ref()).map(|(_, s)| s.clone()) }
 }
 
-impl Error for GenError {}
+impl Error for GenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|e| e.as_ref() as &(dyn Error + 'static))
+    }
+}
 
 impl From<std::io::Error> for GenError {
     fn from(e: std::io::Error) -> Self {
-        GenError::new_with(format!("error from io, namely {}", e.to_string()).as_str())
+        GenError::new_with(format!("error from io, namely {}", e).as_str()).with_code(ErrorCode::Io).with_source(e)
     }
 }
 
@@ -52,18 +175,66 @@ impl From<std::string::String> for GenError {
 
 impl From<nom::Err<nom::error::Error<&str>>> for GenError {
     fn from(e: nom::Err<nom::error::Error<&str>>) -> Self {
-        GenError::new_with(e.to_string().as_str())
+        GenError::new_with(e.to_string().as_str()).with_code(ErrorCode::Syntax)
     }
 }
 
 impl From<serde_json::Error> for GenError {
     fn from(e: serde_json::Error) -> Self {
-        GenError::new_with(e.to_string().as_str())
+        GenError::new_with(e.to_string().as_str()).with_code(ErrorCode::InvalidJson).with_source(e)
     }
 }
 
 impl Display for GenError {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        write!(f, "error while parsing a generator func, reason: {} and type: {:?}", self.reason, self.tpe)
+        match self.tpe {
+            Parser => write!(f, "parser error [{}]", self.code)?,
+            Sender => write!(f, "sender error [{}]", self.code)?,
+            Generator => write!(f, "generator error [{}]", self.code)?,
+            Common => write!(f, "error [{}]", self.code)?,
+        }
+        let mut place = vec![];
+        if let Some(path) = self.path() {
+            place.push(format!("field '{}'", path))
+        }
+        if let Some(generator) = self.generator() {
+            place.push(format!("generator '{}'", generator))
+        }
+        if let (Some(expression), Some(span)) = (self.expression(), self.span()) {
+            place.push(format!("span {}..{} of '{}'", span.start, span.end, expression))
+        }
+        if !place.is_empty() {
+            write!(f, " ({})", place.join(", "))?
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{GenError, ErrorCode};
+    use std::error::Error;
+
+    #[test]
+    fn display_test() {
+        let e = GenError::new_with_in_parser("the array length should be a positive number")
+            .with_code(ErrorCode::InvalidArguments)
+            .with_generator("array")
+            .with_span("str(3) -> array(-1)", 10..19)
+            .in_field("tags")
+            .in_field("user");
+        assert_eq!(e.path(), Some("user.tags"));
+        assert_eq!(e.to_string(), "parser error [invalid-arguments] \
+        (field 'user.tags', generator 'array', span 10..19 of 'str(3) -> array(-1)'): \
+        the array length should be a positive number");
+        assert_eq!(GenError::new_with_in_sender("the server is down").to_string(),
+                   "sender error [sending]: the server is down");
+
+        let e: GenError = std::io::Error::new(std::io::ErrorKind::NotFound, "no file").into();
+        assert_eq!(e.code(), ErrorCode::Io);
+        assert_eq!(e.source().map(|s| s.to_string()), Some("no file".to_string()));
+        let e: GenError = serde_json::from_str::<serde_json::Value>("{").unwrap_err().into();
+        assert_eq!(e.code(), ErrorCode::InvalidJson);
+        assert!(e.source().is_some());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,236 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rand::Rng;
+use serde_json::Value;
+use crate::generator::{GeneratorFunc, Random, Func, new_func, ValueType};
+use crate::error::GenError;
+
+/// The output of the generated date time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DtFormat {
+    /// the string formatted with the pattern (e.g. `%Y-%m-%d %H:%M:%S`)
+    Pattern(String),
+    /// the number of seconds since the epoch
+    EpochSeconds,
+    /// the number of milliseconds since the epoch
+    EpochMillis,
+}
+
+impl DtFormat {
+    /// `epoch` and `epoch_ms` stand for the numbers since the epoch, any other string is the pattern.
+    pub fn new(format: &str) -> Self {
+        match format {
+            "epoch" => DtFormat::EpochSeconds,
+            "epoch_ms" => DtFormat::EpochMillis,
+            p => DtFormat::Pattern(p.to_string())
+        }
+    }
+}
+
+/// The range of the generated date time in milliseconds since the epoch.
+#[derive(Debug, Clone, PartialEq)]
+enum DtRange {
+    /// the fixed bounds.
+    Fixed(i64, i64),
+    /// the bounds shifted from the moment of generation.
+    FromNow(i64, i64),
+}
+
+///The function generated random date time from the range inclusively.
+pub struct RandomDateTime {
+    /// the range of the values.
+    range: DtRange,
+    /// the format of generating output.
+    format: DtFormat,
+    /// the time zone of generating output.
+    tz: Tz,
+    /// the generated random.
+    rng: Random,
+}
+
+impl RandomDateTime {
+    /// the current date time.
+    pub fn now(format: DtFormat, tz: &str) -> Result<Self, GenError> {
+        RandomDateTime::new(DtRange::FromNow(0, 0), format, tz)
+    }
+
+    /// the date time between the start and the end inclusively.
+    /// The bounds can be either a date (`2020-01-01`), a date time (`2020-01-01 10:00:00`)
+    /// or a date time with an offset (`2020-01-01T10:00:00+02:00`).
+    /// The bounds without an offset are taken in the given time zone and the end date covers the whole day.
+    pub fn between(start: &str, end: &str, format: DtFormat, tz: &str) -> Result<Self, GenError> {
+        let zone = parse_tz(tz)?;
+        let range = DtRange::Fixed(parse_dt(start, &zone, false)?, parse_dt(end, &zone, true)?);
+        RandomDateTime::new(range, format, tz)
+    }
+
+    /// the date time between the moments shifted from now with the offsets (e.g. `-30d`, `+2h`).
+    pub fn offset(from: &str, to: &str, format: DtFormat, tz: &str) -> Result<Self, GenError> {
+        RandomDateTime::new(DtRange::FromNow(parse_offset(from)?, parse_offset(to)?), format, tz)
+    }
+
+    fn new(range: DtRange, format: DtFormat, tz: &str) -> Result<Self, GenError> {
+        match range {
+            DtRange::Fixed(s, e) | DtRange::FromNow(s, e) if s > e =>
+                Err(GenError::new_with_in_parser("the start of the date time range should not be after the end")),
+            _ => Ok(RandomDateTime { range, format, tz: parse_tz(tz)?, rng: Random::new() })
+        }
+    }
+}
+
+fn parse_tz(tz: &str) -> Result<Tz, GenError> {
+    tz.parse().map_err(|_| GenError::new_with_in_parser(
+        format!("the time zone '{}' is unknown, it should be a name like UTC or Europe/Berlin", tz).as_str()))
+}
+
+fn parse_dt(v: &str, tz: &Tz, end_of_day: bool) -> Result<i64, GenError> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(v) {
+        return Ok(dt.timestamp_millis());
+    }
+    let naive =
+        ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(v, f).ok())
+            .or_else(|| NaiveDate::parse_from_str(v, "%Y-%m-%d").ok().map(|d| {
+                if end_of_day { d.and_hms_milli(23, 59, 59, 999) } else { d.and_hms(0, 0, 0) }
+            }))
+            .ok_or_else(|| GenError::new_with_in_parser(
+                format!("the date time '{}' should look like 2020-01-01, 2020-01-01 10:00:00 or 2020-01-01T10:00:00+02:00", v).as_str()))?;
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+        .ok_or_else(|| GenError::new_with_in_parser(
+            format!("the date time '{}' does not exist in the time zone {}", v, tz).as_str()))
+}
+
+/// parses the offset in milliseconds, e.g. `-30d`, `+2h`, `15m`, `0`.
+fn parse_offset(v: &str) -> Result<i64, GenError> {
+    let err = || GenError::new_with_in_parser(
+        format!("the offset '{}' should be a number with the unit (ms,s,m,h,d,w) like -30d", v).as_str());
+    if v == "0" {
+        return Ok(0);
+    }
+    let idx = v.find(|c: char| c.is_ascii_alphabetic()).ok_or_else(err)?;
+    let (num, unit) = v.split_at(idx);
+    let num: i64 = num.trim_start_matches('+').parse().map_err(|_| err())?;
+    let duration = match unit {
+        "ms" => Duration::milliseconds(num),
+        "s" => Duration::seconds(num),
+        "m" => Duration::minutes(num),
+        "h" => Duration::hours(num),
+        "d" => Duration::days(num),
+        "w" => Duration::weeks(num),
+        _ => return Err(err())
+    };
+    Ok(duration.num_milliseconds())
+}
+
+impl GeneratorFunc for RandomDateTime {
+    fn next_value(&mut self) -> Value {
+        let (start, end) = match self.range {
+            DtRange::Fixed(s, e) => (s, e),
+            DtRange::FromNow(s, e) => {
+                let now = Utc::now().timestamp_millis();
+                (now + s, now + e)
+            }
+        };
+        let millis = if start < end { self.rng.with(|r| r.gen_range(start, end + 1)) } else { start };
+        match &self.format {
+            DtFormat::EpochSeconds => Value::from(millis.div_euclid(1000)),
+            DtFormat::EpochMillis => Value::from(millis),
+            DtFormat::Pattern(p) => {
+                let time = self.tz.timestamp_millis(millis);
+                Value::from(
+                    if p.is_empty() { time.to_string() } else { time.format(p.as_str()).to_string() }
+                )
+            }
+        }
+    }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        match self.format {
+            DtFormat::EpochSeconds | DtFormat::EpochMillis => Some(ValueType::Integer),
+            DtFormat::Pattern(_) => Some(ValueType::String),
+        }
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomDateTime { range: self.range.clone(), format: self.format.clone(), tz: self.tz, rng: Random::new() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::datetime::{RandomDateTime, DtFormat, parse_offset};
+    use crate::generator::GeneratorFunc;
+    use serde_json::Value;
+    use chrono::{NaiveDateTime, Utc};
+
+    #[test]
+    fn between_test() {
+        let mut g = RandomDateTime::between("2020-01-01", "2020-01-03", DtFormat::new("%Y-%m-%d"), "UTC").unwrap();
+        for _ in 0..100 {
+            if_let!(g.next_value() => Value::String(el)
+                => assert!(["2020-01-01", "2020-01-02", "2020-01-03"].contains(&el.as_str()), "{}", el));
+        }
+
+        let mut g = RandomDateTime::between("2020-01-01 10:00:00", "2020-01-01 10:00:00",
+                                            DtFormat::new("%Y-%m-%d %H:%M:%S %z"), "Europe/Berlin").unwrap();
+        assert_eq!(g.next_value(), Value::from("2020-01-01 10:00:00 +0100"));
+
+        let mut g = RandomDateTime::between("2020-01-01T00:00:00+02:00", "2020-01-01T00:00:00+02:00",
+                                            DtFormat::new("%Y-%m-%d %H:%M"), "UTC").unwrap();
+        assert_eq!(g.next_value(), Value::from("2019-12-31 22:00"));
+
+        let mut g = RandomDateTime::between("2020-01-01", "2020-01-01 00:00:01", DtFormat::EpochSeconds, "UTC").unwrap();
+        if_let!(g.next_value() => Value::Number(n) => assert!([1577836800, 1577836801].contains(&n.as_i64().unwrap())));
+
+        let mut g = RandomDateTime::between("2020-01-01 00:00", "2020-01-01 00:00", DtFormat::EpochMillis, "UTC").unwrap();
+        assert_eq!(g.next_value(), Value::from(1577836800000_i64));
+    }
+
+    #[test]
+    fn offset_test() {
+        let mut g = RandomDateTime::offset("-30d", "+2h", DtFormat::EpochMillis, "UTC").unwrap();
+        for _ in 0..100 {
+            let now = Utc::now().timestamp_millis();
+            if_let!(g.next_value() => Value::Number(n) => {
+                let v = n.as_i64().unwrap();
+                assert!(v >= now - 30 * 24 * 3600 * 1000 && v <= now + 2 * 3600 * 1000 + 1000, "{}", v)
+            });
+        }
+
+        let mut g = RandomDateTime::offset("-1d", "-1d", DtFormat::new("%Y-%m-%d"), "UTC").unwrap();
+        let yesterday = (Utc::now() - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+        assert_eq!(g.next_value(), Value::from(yesterday));
+
+        let mut g = RandomDateTime::now(DtFormat::new("%Y-%m-%d %H:%M:%S"), "Asia/Tokyo").unwrap();
+        if_let!(g.next_value() => Value::String(el)
+            => assert!(NaiveDateTime::parse_from_str(el.as_str(), "%Y-%m-%d %H:%M:%S").is_ok()));
+
+        assert_eq!(parse_offset("0").unwrap(), 0);
+        assert_eq!(parse_offset("+15m").unwrap(), 15 * 60 * 1000);
+        assert_eq!(parse_offset("-2w").unwrap(), -14 * 24 * 3600 * 1000);
+        assert_eq!(parse_offset("100ms").unwrap(), 100);
+    }
+
+    #[test]
+    fn failed_test() {
+        if_let!(RandomDateTime::between("2020-01-02", "2020-01-01", DtFormat::EpochSeconds, "UTC").err()
+            => Some(e) => assert!(e.to_string().contains("should not be after")));
+        if_let!(RandomDateTime::between("2020/01/02", "2020-01-03", DtFormat::EpochSeconds, "UTC").err()
+            => Some(e) => assert!(e.to_string().contains("should look like")));
+        if_let!(RandomDateTime::between("2020-01-01", "2020-01-03", DtFormat::EpochSeconds, "Mars/Olympus").err()
+            => Some(e) => assert!(e.to_string().contains("is unknown")));
+        if_let!(RandomDateTime::offset("-30", "0", DtFormat::EpochSeconds, "UTC").err()
+            => Some(e) => assert!(e.to_string().contains("the offset '-30'")));
+        if_let!(RandomDateTime::offset("1y", "0", DtFormat::EpochSeconds, "UTC").err()
+            => Some(e) => assert!(e.to_string().contains("the offset '1y'")));
+        if_let!(RandomDateTime::offset("1d", "0", DtFormat::EpochSeconds, "UTC").err()
+            => Some(e) => assert!(e.to_string().contains("should not be after")));
+    }
+}
@@ -32,6 +32,16 @@ impl FromStringTo for i32 {
     }
 }
 
+impl FromStringTo for f64 {
+    fn parse(v: &str, rem_spaces: bool) -> Result<Self, GenError> {
+        let value = if rem_spaces { trim_spaces(v) } else { v.to_string() };
+        value.parse::<f64>()
+            .map_err(|e| GenError::new_with_in_parser(
+                format!("impossible to convert string to f64 due to {}", e).as_str()
+            ))
+    }
+}
+
 impl FromStringTo for usize {
     fn parse(v: &str, rem_spaces: bool) -> Result<Self, GenError> {
         let value = if rem_spaces { trim_spaces(v) } else { v.to_string() };
@@ -1,14 +1,15 @@
-use crate::generator::{GeneratorFunc, Generator, Func, new_func};
-use rand::distributions::Alphanumeric;
-use rand::prelude::ThreadRng;
-use uuid::Uuid;
+use crate::generator::{GeneratorFunc, Generator, Func, new_func, Random, ValueType};
+use rand::distributions::{Alphanumeric, Distribution, WeightedIndex};
+use uuid::{Builder, Variant, Version};
 use rand::Rng;
-use chrono::Utc;
 use rand::seq::SliceRandom;
 use std::fs::File;
 use std::io::{Read, Error};
 use serde_json::Value;
 use std::iter::FromIterator;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicI32, Ordering};
 use crate::generator::from_string::FromStringTo;
 use crate::error::GenError;
 
@@ -19,41 +20,95 @@ impl GeneratorFunc for Null {
     fn next_value(&mut self) -> Value {
         Value::Null
     }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(Null {}))
+    }
 }
 
 /// The structure, generating uuid.
-pub struct UUID {}
+pub struct UUID {
+    /// the generated random.
+    rng: Random
+}
+
+impl UUID {
+    pub fn new() -> Self {
+        UUID { rng: Random::new() }
+    }
+}
+
+impl Default for UUID {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl GeneratorFunc for UUID {
     fn next_value(&mut self) -> Value {
-        Value::from(format!("{}", Uuid::new_v4()))
+        let bytes = self.rng.with(|r| r.gen());
+        let uuid = Builder::from_bytes(bytes)
+            .set_variant(Variant::RFC4122)
+            .set_version(Version::Random)
+            .build();
+        Value::from(format!("{}", uuid))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::String)
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(UUID::new()))
     }
 }
 
-/// The structure generating integers in sequence
+/// The structure generating integers in sequence.
+/// The copies of the sequence working in different threads share the last value,
+/// the jsons generated in parallel take the values in the order of the jsons (see `crate::generate_par`).
 pub struct Sequence {
-    /// the initial value. The value is used as a ground to start striding therefore the first value is going to be `val + step`.
-    pub val: i32,
+    /// the last value. The initial value is used as a ground to start striding therefore the first value is going to be `val + step`.
+    val: Arc<AtomicI32>,
     /// the stride of the calculation.
-    pub step: i32,
+    step: i32,
 }
 
+impl Sequence {
+    pub fn new(val: i32, step: i32) -> Self {
+        Sequence { val: Arc::new(AtomicI32::new(val)), step }
+    }
+}
 
 impl GeneratorFunc for Sequence {
     fn next_value(&mut self) -> Value {
-        self.val += self.step;
-        Value::from(self.val)
+        Value::from(self.val.fetch_add(self.step, Ordering::SeqCst).wrapping_add(self.step))
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::Integer)
+    }
+
+    fn shares_state(&self) -> bool {
+        true
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(Sequence { val: self.val.clone(), step: self.step }))
     }
 }
 
 /// The structure generating random booleans
 pub struct RandomBool {
-    rng: ThreadRng
+    rng: Random
 }
 
 impl RandomBool {
     pub fn new() -> Self {
-        RandomBool { rng: rand::thread_rng() }
+        RandomBool { rng: Random::new() }
     }
 }
 
@@ -65,7 +120,19 @@ impl Default for RandomBool {
 
 impl GeneratorFunc for RandomBool {
     fn next_value(&mut self) -> Value {
-        Value::from(self.rng.gen_bool(0.4))
+        Value::from(self.rng.with(|r| r.gen_bool(0.4)))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::Bool)
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomBool::new()))
     }
 }
 
@@ -76,21 +143,73 @@ pub struct RandomInt {
     /// The end exclusively.
     end: i32,
     /// the generated random.
-    rng: ThreadRng,
+    rng: Random,
 }
 
 impl RandomInt {
     pub fn new(start: i32, end: i32) -> Self {
-        RandomInt { start, end, rng: rand::thread_rng() }
+        RandomInt { start, end, rng: Random::new() }
     }
 }
 
 impl GeneratorFunc for RandomInt {
     fn next_value(&mut self) -> Value {
+        let (start, end) = (self.start, self.end);
         Value::from(
-            self.rng.gen_range(self.start, self.end)
+            self.rng.with(|r| r.gen_range(start, end))
         )
     }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::Integer)
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomInt::new(self.start, self.end)))
+    }
+}
+
+/// The structure generating random float rounded to the given precision.
+pub struct RandomFloat {
+    /// The start inclusively.
+    start: f64,
+    /// The end exclusively.
+    end: f64,
+    /// The number of decimal places.
+    precision: u32,
+    /// the generated random.
+    rng: Random,
+}
+
+impl RandomFloat {
+    pub fn new(start: f64, end: f64, precision: u32) -> Self {
+        RandomFloat { start, end, precision, rng: Random::new() }
+    }
+}
+
+impl GeneratorFunc for RandomFloat {
+    fn next_value(&mut self) -> Value {
+        let (start, end) = (self.start, self.end);
+        let factor = 10_f64.powi(self.precision as i32);
+        let v: f64 = self.rng.with(|r| r.gen_range(start, end));
+        Value::from((v * factor).round() / factor)
+    }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::Float)
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomFloat::new(self.start, self.end, self.precision)))
+    }
 }
 
 ///The function generated random string composing from prefix + generated chunk + suffix
@@ -98,7 +217,7 @@ pub struct RandomString {
     /// The generated chunk length
     len: usize,
     /// the generated random.
-    rng: ThreadRng,
+    rng: Random,
     /// the prefix
     prefix: String,
     /// the suffix.
@@ -111,13 +230,13 @@ impl RandomString {
             len,
             prefix,
             postfix,
-            rng: rand::thread_rng(),
+            rng: Random::new(),
         }
     }
     pub fn new(len: usize) -> Self {
         RandomString {
             len,
-            rng: rand::thread_rng(),
+            rng: Random::new(),
             prefix: String::new(),
             postfix: String::new(),
         }
@@ -127,30 +246,86 @@ impl RandomString {
 
 impl GeneratorFunc for RandomString {
     fn next_value(&mut self) -> Value {
-        let random_str = String::from_iter(
-            self.rng
-                .sample_iter(&Alphanumeric)
-                .take(self.len));
+        let len = self.len;
+        let random_str = self.rng.with(|r| String::from_iter(
+            r.sample_iter(&Alphanumeric)
+                .take(len)));
         Value::from(format!("{}{}{}", self.prefix, random_str, self.postfix))
     }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::String)
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomString::new_with(self.len, self.prefix.clone(), self.postfix.clone())))
+    }
+}
+
+///The function composing a string from the pattern, replacing the placeholders `{}` with the values of the generators.
+pub struct Format {
+    /// the chunks of the pattern between the placeholders.
+    chunks: Vec<String>,
+    /// the generators filling the placeholders.
+    args: Vec<Generator>,
 }
 
-/// The function generated current data time
-pub struct CurrentDateTime {
-    /// the format of generating output
-    pub format: String
+impl Format {
+    pub fn new(pattern: &str, args: Vec<Generator>) -> Result<Self, GenError> {
+        let chunks: Vec<String> = pattern.split("{}").map(String::from).collect();
+        if chunks.len() - 1 != args.len() {
+            Err(GenError::new_with_in_parser(
+                format!("the pattern '{}' has {} placeholders but {} generators are given",
+                        pattern, chunks.len() - 1, args.len()).as_str()))
+        } else {
+            Ok(Format { chunks, args })
+        }
+    }
 }
 
-impl GeneratorFunc for CurrentDateTime {
+impl GeneratorFunc for Format {
     fn next_value(&mut self) -> Value {
-        let time = Utc::now();
-        Value::from(
-            if self.format.is_empty() {
-                time.to_string()
-            } else {
-                time.format(self.format.as_str()).to_string()
+        self.try_next().ok().flatten().unwrap_or(Value::Null)
+    }
+
+    fn try_next(&mut self) -> Result<Option<Value>, GenError> {
+        let mut res = self.chunks[0].clone();
+        for (arg, chunk) in self.args.iter().zip(self.chunks.iter().skip(1)) {
+            match arg.try_next()?.unwrap_or(Value::Null) {
+                Value::String(v) => res.push_str(v.as_str()),
+                v => res.push_str(v.to_string().as_str()),
             }
-        )
+            res.push_str(chunk.as_str())
+        }
+        Ok(Some(Value::from(res)))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        self.args.iter().for_each(|a| a.set_random(random.clone()))
+    }
+
+    fn references(&self) -> Vec<String> {
+        self.args.iter().flat_map(|a| a.references()).collect()
+    }
+
+    fn resolve(&mut self, path: &str, value: &Value) {
+        self.args.iter().for_each(|a| a.resolve(path, value))
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::String)
+    }
+
+    fn shares_state(&self) -> bool {
+        self.args.iter().any(|a| a.shares_state())
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(Format { chunks: self.chunks.clone(), args: self.args.iter().map(|a| a.fork()).collect() }))
     }
 }
 
@@ -158,25 +333,88 @@ impl GeneratorFunc for CurrentDateTime {
 pub struct RandomFromList<T: Into<Value>> {
     /// the list of values to pull out.
     values: Vec<T>,
+    /// the distribution of the weights of the values, if the values are weighted.
+    weights: Option<WeightedIndex<f64>>,
     /// the generated random
-    rng: ThreadRng,
+    rng: Random,
 }
 
 impl<T: Into<Value> + Clone> RandomFromList<T> {
     pub fn new(values: Vec<T>) -> Self {
-        RandomFromList { values, rng: rand::thread_rng() }
+        RandomFromList { values, weights: None, rng: Random::new() }
+    }
+    /// the values are pulled out with the probability proportional to their weights.
+    pub fn new_weighted(values: Vec<(T, f64)>) -> Result<Self, GenError> {
+        let weights = WeightedIndex::new(values.iter().map(|(_, w)| *w))
+            .map_err(|e| GenError::new_with_in_parser(format!("the weights are invalid: {}", e).as_str()))?;
+        Ok(RandomFromList {
+            values: values.into_iter().map(|(v, _)| v).collect(),
+            weights: Some(weights),
+            rng: Random::new(),
+        })
     }
 }
 
 
 impl<T> GeneratorFunc for RandomFromList<T>
-    where T: Into<Value> + Clone {
+    where T: Into<Value> + Clone + Send + 'static {
     fn next_value(&mut self) -> Value {
-        match self.values.choose(&mut self.rng) {
+        let values = &self.values;
+        let chosen = match &self.weights {
+            Some(weights) => self.rng.with(|r| values.get(weights.sample(r))),
+            None => self.rng.with(|r| values.choose(r)),
+        };
+        match chosen {
             None => Value::Null,
             Some(v) => v.clone().into(),
         }
     }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        self.values.first().and_then(|v| ValueType::of(&v.clone().into()))
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomFromList { values: self.values.clone(), weights: self.weights.clone(), rng: Random::new() }))
+    }
+}
+
+///The function returning the value generated for another field of the same json.
+pub struct Reference {
+    /// the path to the field, the names of the fields are separated with dots.
+    path: String,
+    /// the value generated for the field.
+    value: Value,
+}
+
+impl Reference {
+    pub fn new(path: &str) -> Self {
+        Reference { path: path.to_string(), value: Value::Null }
+    }
+}
+
+impl GeneratorFunc for Reference {
+    fn next_value(&mut self) -> Value {
+        self.value.clone()
+    }
+
+    fn references(&self) -> Vec<String> {
+        vec![self.path.clone()]
+    }
+
+    fn resolve(&mut self, path: &str, value: &Value) {
+        if self.path == path {
+            self.value = value.clone()
+        }
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(Reference::new(&self.path)))
+    }
 }
 
 //todo in general with small files, having them in the memory is fine but if it is going to be a pitfall,
@@ -191,19 +429,56 @@ pub struct RandomFromFile<T: FromStringTo + Clone + Into<Value>> {
 impl<T: FromStringTo + Clone + Into<Value>> RandomFromFile<T> {
     pub fn new(path: &str, delim: &str) -> Result<Self, GenError> {
         let values = process_string(read_file_into_string(path)?, delim)?;
-        let rng = Default::default();
         Ok(
             RandomFromFile {
-                delegate: RandomFromList { values, rng },
+                delegate: RandomFromList::new(values),
+            }
+        )
+    }
+    /// every value in the file is followed by the weight, separated with colon: `value:weight`
+    pub fn new_weighted(path: &str, delim: &str) -> Result<Self, GenError> {
+        let values = process_string::<String>(read_file_into_string(path)?, delim)?
+            .iter()
+            .map(|e| split_weight(e, delim != " "))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(
+            RandomFromFile {
+                delegate: RandomFromList::new_weighted(values)?,
             }
         )
     }
 }
 
-impl<T: Clone + FromStringTo + Into<Value>> GeneratorFunc for RandomFromFile<T> {
+/// splits the string `value:weight` into the value and the weight.
+fn split_weight<T: FromStringTo>(v: &str, trim_spaces: bool) -> Result<(T, f64), GenError> {
+    match v.rfind(':') {
+        Some(idx) => {
+            let weight = FromStringTo::parse(&v[idx + 1..], true)
+                .map_err(|_| GenError::new_with_in_parser(
+                    format!("the weight of the value '{}' should be a number", v).as_str()))?;
+            Ok((FromStringTo::parse(&v[..idx], trim_spaces)?, weight))
+        }
+        None => Err(GenError::new_with_in_parser(
+            format!("the value '{}' should have a weight separated with colon", v).as_str()))
+    }
+}
+
+impl<T: Clone + FromStringTo + Into<Value> + Send + 'static> GeneratorFunc for RandomFromFile<T> {
     fn next_value(&mut self) -> Value {
         self.delegate.next_value()
     }
+
+    fn set_random(&mut self, random: Random) {
+        self.delegate.set_random(random)
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        self.delegate.value_type()
+    }
+
+    fn fork(&self) -> Option<Func> {
+        self.delegate.fork()
+    }
 }
 
 fn process_string<T: FromStringTo>(v: String, d: &str) -> Result<Vec<T>, GenError> {
@@ -233,18 +508,30 @@ pub fn read_file_into_string(path: &str) -> Result<String, Error> {
 
 ///The function generated the json value.
 pub struct RandomArray {
-    /// the length of the array
+    /// the length of the array (the minimal length if the length is random)
     len: usize,
+    /// the maximal length of the array inclusively
+    max_len: usize,
     /// the delegate function
     delegate: Option<Generator>,
+    /// the generated random to pick the length.
+    rng: Random,
 }
 
 impl RandomArray {
     pub fn new(len: usize, delegate: Generator) -> Self {
-        RandomArray { len, delegate: Some(delegate) }
+        RandomArray::new_range(len, len, delegate)
     }
     pub fn new_size(len: usize) -> Self {
-        RandomArray { len, delegate: None }
+        RandomArray::new_range_size(len, len)
+    }
+    /// the array having the random length from `len` to `max_len` inclusively
+    pub fn new_range(len: usize, max_len: usize, delegate: Generator) -> Self {
+        RandomArray { len, max_len, delegate: Some(delegate), rng: Random::new() }
+    }
+    /// the array having the random length from `len` to `max_len` inclusively
+    pub fn new_range_size(len: usize, max_len: usize) -> Self {
+        RandomArray { len, max_len, delegate: None, rng: Random::new() }
     }
     pub fn len(&self) -> usize {
         self.len
@@ -254,20 +541,325 @@ impl RandomArray {
 
 impl GeneratorFunc for RandomArray {
     fn next_value(&mut self) -> Value {
-        Value::Array(
-            (0..self.len).map(|_| self.delegate.as_ref().map(|e| e.next()).unwrap_or(Value::Null)).collect()
-        )
+        self.try_next().ok().flatten().unwrap_or(Value::Null)
+    }
+
+    fn try_next(&mut self) -> Result<Option<Value>, GenError> {
+        let (min, max) = (self.len, self.max_len);
+        let len = if min < max { self.rng.with(|r| r.gen_range(min, max + 1)) } else { min };
+        let values: Result<Vec<Value>, GenError> = (0..len).map(|_| delegate_next(&self.delegate)).collect();
+        Ok(Some(Value::Array(values?)))
     }
 
     fn merge(&self, another_gf: Func) -> Result<Func, GenError> {
-        Ok(new_func(RandomArray::new(self.len, Generator { function: another_gf })))
+        Ok(new_func(RandomArray {
+            len: self.len,
+            max_len: self.max_len,
+            delegate: Some(Generator { function: another_gf }),
+            rng: self.rng.clone(),
+        }))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.set_random(random.clone())
+        }
+        self.rng = random
+    }
+
+    fn references(&self) -> Vec<String> {
+        self.delegate.as_ref().map(|d| d.references()).unwrap_or_default()
+    }
+
+    fn resolve(&mut self, path: &str, value: &Value) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.resolve(path, value)
+        }
+    }
+
+    fn max_len(&self) -> Option<usize> {
+        Some(self.max_len)
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::Array)
+    }
+
+    fn shares_state(&self) -> bool {
+        self.delegate.as_ref().map(|d| d.shares_state()).unwrap_or_default()
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomArray {
+            len: self.len,
+            max_len: self.max_len,
+            delegate: self.delegate.as_ref().map(|d| d.fork()),
+            rng: Random::new(),
+        }))
+    }
+}
+
+/// the value of the delegate function or null if the delegate is absent or generates nothing.
+fn delegate_next(delegate: &Option<Generator>) -> Result<Value, GenError> {
+    match delegate {
+        Some(d) => d.try_next().map(|v| v.unwrap_or(Value::Null)),
+        None => Ok(Value::Null)
+    }
+}
+
+///The function generated null instead of the value of the delegate with the given probability.
+pub struct Nullable {
+    /// the probability to get null
+    probability: f64,
+    /// the delegate function
+    delegate: Option<Generator>,
+    /// the generated random.
+    rng: Random,
+}
+
+impl Nullable {
+    pub fn new(probability: f64) -> Result<Self, GenError> {
+        Ok(Nullable { probability: check_probability(probability)?, delegate: None, rng: Random::new() })
+    }
+}
+
+impl GeneratorFunc for Nullable {
+    fn next_value(&mut self) -> Value {
+        self.try_next().ok().flatten().unwrap_or(Value::Null)
+    }
+
+    fn try_next(&mut self) -> Result<Option<Value>, GenError> {
+        let p = self.probability;
+        if self.rng.with(|r| r.gen_bool(p)) {
+            Ok(Some(Null {}.next_value()))
+        } else {
+            delegate_next(&self.delegate).map(Some)
+        }
+    }
+
+    fn merge(&self, another_gf: Func) -> Result<Func, GenError> {
+        Ok(new_func(Nullable {
+            probability: self.probability,
+            delegate: Some(Generator { function: another_gf }),
+            rng: self.rng.clone(),
+        }))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.set_random(random.clone())
+        }
+        self.rng = random
+    }
+
+    fn references(&self) -> Vec<String> {
+        self.delegate.as_ref().map(|d| d.references()).unwrap_or_default()
+    }
+
+    fn resolve(&mut self, path: &str, value: &Value) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.resolve(path, value)
+        }
+    }
+
+    fn max_len(&self) -> Option<usize> {
+        self.delegate.as_ref().and_then(|d| d.max_len())
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        self.delegate.as_ref().and_then(|d| d.value_type())
+    }
+
+    fn shares_state(&self) -> bool {
+        self.delegate.as_ref().map(|d| d.shares_state()).unwrap_or_default()
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(Nullable {
+            probability: self.probability,
+            delegate: self.delegate.as_ref().map(|d| d.fork()),
+            rng: Random::new(),
+        }))
+    }
+}
+
+///The function omitting the field in the json with the given probability.
+/// Being placed not in a field, e.g. in an array, it generates null instead of omitting.
+pub struct Optional {
+    /// the probability to omit the field
+    probability: f64,
+    /// the delegate function
+    delegate: Option<Generator>,
+    /// the generated random.
+    rng: Random,
+}
+
+impl Optional {
+    pub fn new(probability: f64) -> Result<Self, GenError> {
+        Ok(Optional { probability: check_probability(probability)?, delegate: None, rng: Random::new() })
+    }
+}
+
+impl GeneratorFunc for Optional {
+    fn next_value(&mut self) -> Value {
+        self.next_optional().unwrap_or(Value::Null)
+    }
+
+    fn next_optional(&mut self) -> Option<Value> {
+        self.try_next().unwrap_or(Some(Value::Null))
+    }
+
+    fn try_next(&mut self) -> Result<Option<Value>, GenError> {
+        let p = self.probability;
+        if self.rng.with(|r| r.gen_bool(p)) {
+            Ok(None)
+        } else {
+            delegate_next(&self.delegate).map(Some)
+        }
+    }
+
+    fn merge(&self, another_gf: Func) -> Result<Func, GenError> {
+        Ok(new_func(Optional {
+            probability: self.probability,
+            delegate: Some(Generator { function: another_gf }),
+            rng: self.rng.clone(),
+        }))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.set_random(random.clone())
+        }
+        self.rng = random
+    }
+
+    fn references(&self) -> Vec<String> {
+        self.delegate.as_ref().map(|d| d.references()).unwrap_or_default()
+    }
+
+    fn resolve(&mut self, path: &str, value: &Value) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.resolve(path, value)
+        }
+    }
+
+    fn max_len(&self) -> Option<usize> {
+        self.delegate.as_ref().and_then(|d| d.max_len())
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        self.delegate.as_ref().and_then(|d| d.value_type())
+    }
+
+    fn shares_state(&self) -> bool {
+        self.delegate.as_ref().map(|d| d.shares_state()).unwrap_or_default()
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(Optional {
+            probability: self.probability,
+            delegate: self.delegate.as_ref().map(|d| d.fork()),
+            rng: Random::new(),
+        }))
+    }
+}
+
+///The function generated the values of the delegate which have not been generated before.
+/// If the delegate repeats the value, it is asked again up to the given number of retries.
+/// The copies of the function working in different threads share the generated values,
+/// the jsons generated in parallel check them in the order of the jsons (see `crate::generate_par`).
+pub struct Unique {
+    /// the number of attempts to get a new value.
+    retries: usize,
+    /// the values which have been generated already.
+    seen: Arc<Mutex<HashSet<String>>>,
+    /// the delegate function
+    delegate: Option<Generator>,
+}
+
+impl Unique {
+    pub fn new(retries: usize) -> Self {
+        Unique { retries, seen: Arc::new(Mutex::new(HashSet::new())), delegate: None }
+    }
+}
+
+impl GeneratorFunc for Unique {
+    fn next_value(&mut self) -> Value {
+        self.try_next().ok().flatten().unwrap_or(Value::Null)
+    }
+
+    fn try_next(&mut self) -> Result<Option<Value>, GenError> {
+        for _ in 0..=self.retries {
+            match self.delegate.as_ref().map(|d| d.try_next()).unwrap_or(Ok(Some(Value::Null)))? {
+                Some(v) => if self.seen.lock().unwrap_or_else(PoisonError::into_inner).insert(v.to_string()) {
+                    return Ok(Some(v));
+                },
+                None => return Ok(None),
+            }
+        }
+        Err(GenError::new_with_in_generator(
+            format!("the unique values are exhausted: no new value after {} retries, {} unique values have been generated",
+                    self.retries, self.seen.lock().unwrap_or_else(PoisonError::into_inner).len()).as_str()))
+    }
+
+    fn merge(&self, another_gf: Func) -> Result<Func, GenError> {
+        Ok(new_func(Unique {
+            retries: self.retries,
+            seen: Arc::new(Mutex::new(HashSet::new())),
+            delegate: Some(Generator { function: another_gf }),
+        }))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.set_random(random)
+        }
+    }
+
+    fn references(&self) -> Vec<String> {
+        self.delegate.as_ref().map(|d| d.references()).unwrap_or_default()
+    }
+
+    fn resolve(&mut self, path: &str, value: &Value) {
+        if let Some(d) = self.delegate.as_ref() {
+            d.resolve(path, value)
+        }
+    }
+
+    fn max_len(&self) -> Option<usize> {
+        self.delegate.as_ref().and_then(|d| d.max_len())
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        self.delegate.as_ref().and_then(|d| d.value_type())
+    }
+
+    fn shares_state(&self) -> bool {
+        true
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(Unique {
+            retries: self.retries,
+            seen: self.seen.clone(),
+            delegate: self.delegate.as_ref().map(|d| d.fork()),
+        }))
+    }
+}
+
+fn check_probability(probability: f64) -> Result<f64, GenError> {
+    if (0.0..=1.0).contains(&probability) {
+        Ok(probability)
+    } else {
+        Err(GenError::new_with_in_parser(
+            format!("the probability {} should be in the range from 0 to 1", probability).as_str()))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::generator::generators::{RandomString, UUID, RandomInt, CurrentDateTime, RandomFromList, read_file_into_string, process_string, RandomFromFile, RandomArray, Null, Sequence};
-    use crate::generator::{GeneratorFunc, Generator};
+    use crate::generator::generators::{RandomString, UUID, RandomInt, RandomFromList, read_file_into_string, process_string, RandomFromFile, RandomArray, Null, Sequence, RandomBool, RandomFloat, Reference, Format, Nullable, Optional, Unique, split_weight};
+    use crate::generator::{GeneratorFunc, Generator, Random};
     use serde_json::Value;
 
     fn gen<T: GeneratorFunc + 'static>(f: T) -> Generator {
@@ -281,17 +873,17 @@ mod tests {
 
     #[test]
     fn random_uuid_test() {
-        if_let!(gen(UUID {}).next() => Value::String(el) => assert_eq!(el.len(), 36));
+        if_let!(gen(UUID::new()).next() => Value::String(el) => assert_eq!(el.len(), 36));
     }
 
     #[test]
     fn sequence_test() {
-        let g1 = gen(Sequence { val: 1, step: 2 });
+        let g1 = gen(Sequence::new(1, 2));
 
         assert_eq!(g1.next().as_i64(), Some(3));
         assert_eq!(g1.next().as_i64(), Some(5));
 
-        let g1 = gen(Sequence { val: 1, step: -1 });
+        let g1 = gen(Sequence::new(1, -1));
 
         assert_eq!(g1.next().as_i64(), Some(0));
         assert_eq!(g1.next().as_i64(), Some(-1));
@@ -306,6 +898,18 @@ mod tests {
         if_let!(g.next().as_i64() => Some(el) => assert!(el >= -1000 && el <= 1000));
     }
 
+    #[test]
+    fn random_float_test() {
+        let g = gen(RandomFloat::new(-10.0, 10.0, 2));
+        for _ in 0..100 {
+            if_let!(g.next().as_f64() => Some(el) => {
+                assert!((-10.0..=10.0).contains(&el));
+                assert_eq!(el, (el * 100.0).round() / 100.0);
+            });
+        }
+        if_let!(gen(RandomFloat::new(1.0, 2.0, 0)).next().as_f64() => Some(el) => assert!(el == 1.0 || el == 2.0));
+    }
+
     #[test]
     fn random_string_test() {
         if_let!(gen(RandomString::new(10)).next() => Value::String(el) => assert_eq!(el.len(), 10));
@@ -320,15 +924,20 @@ mod tests {
     }
 
     #[test]
-    fn current_ts_test() {
-        let x = gen(CurrentDateTime { format: "%Y-%m-%d".to_string() });
-        if_let!(x.next() => x.next() => ());
-
-        let mut x = CurrentDateTime { format: "%Y-%m-%d %H:%M:%S".to_string() };
-        if_let!(x.next_value() => Value::String(el) => {
-            print!("{}", el);
-            assert_eq!(el.len(), 19);
+    fn format_test() {
+        let g = gen(Format::new("id-{}-{}:{}", vec![
+            gen(Sequence::new(0, 1)),
+            gen(RandomString::new(4)),
+            gen(Null {}),
+        ]).unwrap());
+        if_let!(g.next() => Value::String(el) => {
+            assert!(el.starts_with("id-1-"));
+            assert!(el.ends_with(":null"));
+            assert_eq!(el.len(), 14);
         });
+        if_let!(g.next() => Value::String(el) => assert!(el.starts_with("id-2-")));
+
+        if_let!(Format::new("{}", vec![]).err() => Some(e) => assert!(e.to_string().contains("has 1 placeholders but 0")));
     }
 
     #[test]
@@ -344,6 +953,35 @@ mod tests {
         assert_eq!(gen.next(), Value::Null);
     }
 
+    #[test]
+    fn random_from_weighted_list_test() {
+        let g = gen(RandomFromList::new_weighted(vec![("NEW", 70.0), ("PAID", 30.0), ("CANCELLED", 0.0)]).unwrap());
+        let values: Vec<Value> = (0..1000).map(|_| g.next()).collect();
+        let new = values.iter().filter(|v| v.as_str() == Some("NEW")).count();
+        let paid = values.iter().filter(|v| v.as_str() == Some("PAID")).count();
+        assert_eq!(new + paid, 1000);
+        assert!(new > paid);
+
+        assert!(RandomFromList::new_weighted(vec![(1, 0.0), (2, 0.0)]).is_err());
+        assert!(RandomFromList::<i64>::new_weighted(vec![]).is_err());
+        assert!(RandomFromList::new_weighted(vec![(1, -1.0)]).is_err());
+    }
+
+    #[test]
+    fn from_weighted_file_test() {
+        let g = RandomFromFile::<String>::new_weighted(r#"jsons/statuses"#, "\n")
+            .map(Generator::new);
+        if_let!(g => Ok(g) => if_let!(g.next() => Value::String(status) => {
+            assert!(["NEW", "PAID", "CANCELLED"].contains(&status.as_str()))
+        }));
+
+        if_let!(RandomFromFile::<String>::new_weighted(r#"jsons/cities"#, "\n").err()
+            => Some(e) => assert!(e.to_string().contains("should have a weight")));
+        if_let!(split_weight::<String>("a:b", true).err()
+            => Some(e) => assert!(e.to_string().contains("the weight of the value 'a:b' should be a number")));
+        if_let!(split_weight::<i64>(" 10 : 2.5 ", true) => Ok(v) => assert_eq!(v, (10, 2.5)));
+    }
+
     #[test]
     fn from_file_test() {
         let g = RandomFromFile::<i64>::new(r#"jsons/numbers"#, ",")
@@ -354,6 +992,14 @@ mod tests {
             assert!(el > 0 && el < 10)
         }));
 
+        let g = RandomFromFile::<f64>::new(r#"jsons/floats"#, ",")
+            .map(Generator::new);
+
+        if_let!(g => Ok(g) => if_let!(g.next() => Value::Number(el) => {
+            let el = el.as_f64().unwrap();
+            assert!(el > 0.0 && el < 10.0)
+        }));
+
         let g = RandomFromFile::<String>::new(r#"jsons/cities"#, "\n")
             .map(|f| Generator::new(f));
 
@@ -379,7 +1025,7 @@ mod tests {
         assert_eq!(vec, vec![1, 2, 3]);
 
         let err = process_string::<i32>("1,c,3".to_string(), ",");
-        assert_eq!(err.err().unwrap().to_string(), "error while parsing a generator func, reason: impossible to convert string to i32 due to invalid digit found in string and type: Parser");
+        assert_eq!(err.err().unwrap().to_string(), "parser error [syntax]: impossible to convert string to i32 due to invalid digit found in string");
 
         let vec = process_string::<i64>("-1,-2,-3".to_string(), ",").unwrap();
         assert_eq!(vec, vec![-1, -2, -3]);
@@ -391,6 +1037,9 @@ mod tests {
 
         let vec = process_string::<i64>("-1 -2 -3".to_string(), " ").unwrap();
         assert_eq!(vec, vec![-1, -2, -3]);
+
+        let vec = process_string::<f64>("1.5, -2.25 ,3".to_string(), ",").unwrap();
+        assert_eq!(vec, vec![1.5, -2.25, 3.0]);
     }
 
     #[test]
@@ -410,4 +1059,91 @@ mod tests {
             }
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn array_range_test() {
+        let g = gen(RandomArray::new_range(2, 4, gen(Sequence::new(0, 1))));
+        let mut lens = vec![];
+        for _ in 0..100 {
+            if_let!(g.next() => Value::Array(elems) => lens.push(elems.len()));
+        }
+        assert!(lens.iter().all(|l| (2..=4).contains(l)));
+        assert!(lens.contains(&2) && lens.contains(&4));
+
+        if_let!(gen(RandomArray::new_range_size(1, 1)).next() => Value::Array(elems) => assert_eq!(elems, vec![Value::Null]));
+    }
+
+    #[test]
+    fn nullable_test() {
+        let g = Generator::new(Nullable::new(0.5).unwrap()).merge(&gen(RandomInt::new(1, 10))).unwrap();
+        let values: Vec<Value> = (0..100).map(|_| g.next()).collect();
+        assert!(values.iter().any(|v| v.is_null()));
+        assert!(values.iter().any(|v| v.is_number()));
+
+        let g = Generator::new(Nullable::new(0.0).unwrap()).merge(&gen(RandomInt::new(1, 10))).unwrap();
+        assert!((0..100).all(|_| g.next().is_number()));
+
+        assert!(Nullable::new(1.5).is_err());
+    }
+
+    #[test]
+    fn optional_test() {
+        let g = Generator::new(Optional::new(0.5).unwrap()).merge(&gen(RandomInt::new(1, 10))).unwrap();
+        let values: Vec<Option<Value>> = (0..100).map(|_| g.next_optional()).collect();
+        assert!(values.iter().any(|v| v.is_none()));
+        assert!(values.iter().any(|v| v.is_some()));
+
+        let g = Generator::new(Optional::new(1.0).unwrap()).merge(&gen(RandomInt::new(1, 10))).unwrap();
+        assert_eq!(g.next_optional(), None);
+        assert_eq!(g.next(), Value::Null);
+
+        assert!(Optional::new(-0.1).is_err());
+    }
+
+    #[test]
+    fn unique_test() {
+        let g = Generator::new(Unique::new(1000)).merge(&gen(RandomInt::new(1, 6))).unwrap();
+        let mut values: Vec<i64> = (0..5).map(|_| g.try_next().unwrap().unwrap().as_i64().unwrap()).collect();
+        values.sort_unstable();
+        assert_eq!(values, vec![1, 2, 3, 4, 5]);
+        if_let!(g.try_next() => Err(e) => assert!(e.to_string().contains("the unique values are exhausted")));
+
+        let g = gen(RandomArray::new(3, Generator::new(Unique::new(10)).merge(&gen(Sequence::new(1, 0))).unwrap()));
+        if_let!(g.try_next() => Err(e) => assert!(e.to_string().contains("generator error [generation]")));
+
+        let g = Generator::new(Unique::new(0)).merge(&gen(Sequence::new(0, 1))).unwrap();
+        assert_eq!(g.next(), Value::from(1));
+        assert_eq!(g.next(), Value::from(2));
+    }
+
+    #[test]
+    fn reference_test() {
+        let g = gen(RandomArray::new(2, gen(Reference::new("a.b"))));
+        assert_eq!(g.references(), vec!["a.b".to_string()]);
+        assert_eq!(g.next(), Value::Array(vec![Value::Null, Value::Null]));
+
+        g.resolve("a.c", &Value::from(1));
+        g.resolve("a.b", &Value::from(2));
+        assert_eq!(g.next(), Value::Array(vec![Value::from(2), Value::from(2)]));
+    }
+
+    #[test]
+    fn seeded_test() {
+        fn values(seed: u64) -> Vec<Value> {
+            let random = Random::from_seed(seed);
+            let gens = vec![
+                gen(UUID::new()),
+                gen(RandomBool::new()),
+                gen(RandomInt::new(1, 1000)),
+                gen(RandomString::new(10)),
+                gen(RandomFromList::new((1..100).collect())),
+                gen(RandomArray::new(3, gen(RandomInt::new(1, 1000)))),
+            ];
+            gens.iter().for_each(|g| g.set_random(random.clone()));
+            (0..10).flat_map(|_| gens.iter().map(|g| g.next()).collect::<Vec<_>>()).collect()
+        }
+
+        assert_eq!(values(1), values(1));
+        assert_ne!(values(1), values(2));
+    }
+}
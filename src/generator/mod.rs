@@ -2,22 +2,122 @@
 //! The functions which are responsible to generate new json values after parsing.
 pub mod generators;
 pub mod from_string;
+pub mod regex;
+pub mod datetime;
 
 use std::fmt::{Debug, Formatter, Error};
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, Condvar, PoisonError};
 use serde_json::Value;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use crate::error::GenError;
+pub use crate::parser::generators::{GeneratorRegistry, Signature, Param};
 
-/// The trait represents the function to generate jsons
-pub trait GeneratorFunc {
+/// The trait represents the function to generate jsons.
+/// The function can be moved to another thread to generate jsons in parallel.
+pub trait GeneratorFunc: Send {
     /// the method generates a new json value
     fn next_value(&mut self) -> Value;
+    /// the method generates a new json value or nothing if the field should be omitted in the json.
+    fn next_optional(&mut self) -> Option<Value> {
+        Some(self.next_value())
+    }
+    /// the method generates a new json value, nothing if the field should be omitted in the json
+    /// or the error if the function is unable to generate a value (e.g. the unique values are exhausted).
+    fn try_next(&mut self) -> Result<Option<Value>, GenError> {
+        Ok(self.next_optional())
+    }
     /// the method carries a logic how to merge two functions into one.
     /// It can be useful for the compound functions like `RandomArray`
     fn merge(&self, _another_gf: Func) -> Result<Func, GenError> {
         Err(GenError::new_with("the functions are unable to merge in the order"))
     }
+    /// the method replaces the random generator of the function.
+    /// It allows sharing one seeded random generator among all functions to make the generation reproducible.
+    fn set_random(&mut self, _random: Random) {}
+    /// the method returns the paths of the fields which values the function refers to.
+    fn references(&self) -> Vec<String> {
+        vec![]
+    }
+    /// the method passes the value generated for the field the function refers to.
+    fn resolve(&mut self, _path: &str, _value: &Value) {}
+    /// the method returns the maximal number of items if the function generates arrays.
+    /// It allows knowing the shape of the generated values beforehand (e.g. the columns of csv).
+    fn max_len(&self) -> Option<usize> {
+        None
+    }
+    /// the method returns the type of the generated values if it is known beforehand.
+    /// It allows knowing the types of the columns before generating (e.g. the columns of sqlite).
+    fn value_type(&self) -> Option<ValueType> {
+        None
+    }
+    /// the method tells whether the function shares the state with its copies working in other threads
+    /// (e.g. the counter of `Sequence`), so the values depend on the order the copies are called in.
+    /// By default, the function having no copy is shared by all threads itself.
+    fn shares_state(&self) -> bool {
+        self.fork().is_none()
+    }
+    /// the method creates an independent copy of the function to generate values in another thread.
+    /// The state which should stay global (e.g. the counter of `Sequence`) is shared with the copy.
+    /// By default, the function has no copy and it is shared by all threads.
+    fn fork(&self) -> Option<Func> {
+        None
+    }
+}
+
+/// The type of the values generated by the function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    Bool,
+    Integer,
+    Float,
+    String,
+    Array,
+    Object,
+}
+
+impl ValueType {
+    /// the type of the value, nothing for null.
+    pub fn of(value: &Value) -> Option<ValueType> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(ValueType::Bool),
+            Value::Number(n) if n.is_f64() => Some(ValueType::Float),
+            Value::Number(_) => Some(ValueType::Integer),
+            Value::String(_) => Some(ValueType::String),
+            Value::Array(_) => Some(ValueType::Array),
+            Value::Object(_) => Some(ValueType::Object),
+        }
+    }
+}
+
+/// The random generator which can be shared among the generator functions.
+/// By default, every function gets its own random generator seeded from the entropy
+/// but being seeded explicitly and shared, it produces the same sequence of values every run.
+#[derive(Clone, Debug)]
+pub struct Random {
+    rng: Arc<Mutex<StdRng>>
+}
+
+impl Random {
+    /// creates a new random generator seeded from the entropy
+    pub fn new() -> Self {
+        Random { rng: Arc::new(Mutex::new(StdRng::from_entropy())) }
+    }
+    /// creates a new random generator with the given seed
+    pub fn from_seed(seed: u64) -> Self {
+        Random { rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))) }
+    }
+    /// performs the given function with the underlying random generator
+    pub fn with<T, F: FnOnce(&mut StdRng) -> T>(&self, f: F) -> T {
+        f(&mut self.rng.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// for logging purposes
@@ -37,10 +137,39 @@ impl Debug for dyn GeneratorFunc {
     }
 }
 
-type Func = Rc<RefCell<dyn GeneratorFunc>>;
+/// The shared function to generate jsons.
+pub type Func = Arc<Mutex<dyn GeneratorFunc>>;
 
 pub fn new_func<T: GeneratorFunc + 'static>(entity: T) -> Func {
-    Rc::new(RefCell::new(entity))
+    Arc::new(Mutex::new(entity))
+}
+
+/// The order of the jsons generated in parallel.
+/// Every json has the index and the json is passed further only after the jsons having the lower indexes,
+/// therefore the jsons are sent in the order of generating.
+#[derive(Debug, Default)]
+pub struct Turn {
+    /// the index of the json which is able to be passed further.
+    current: Mutex<usize>,
+    changed: Condvar,
+}
+
+impl Turn {
+    pub fn new() -> Self {
+        Turn::default()
+    }
+
+    /// waits for the turn of the json with the index, performs the action and passes the turn to the next json.
+    pub fn finish<T, F: FnOnce() -> T>(&self, idx: usize, f: F) -> T {
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        while *current < idx {
+            current = self.changed.wait(current).unwrap_or_else(PoisonError::into_inner);
+        }
+        let res = f();
+        *current += 1;
+        self.changed.notify_all();
+        res
+    }
 }
 
 /// In general, that is a wrapper on the function `GeneratorFunc`
@@ -51,7 +180,7 @@ pub struct Generator {
 
 impl ToString for Generator {
     fn to_string(&self) -> String {
-        format!("Generator[{:?}]", self.function.clone().borrow().to_string())
+        format!("Generator[{:?}]", self.function.lock().unwrap_or_else(PoisonError::into_inner).to_string())
     }
 }
 
@@ -68,22 +197,68 @@ impl Generator {
         Generator { function: new_func(entity) }
     }
     pub fn next(&self) -> Value {
-        RefCell::borrow_mut(&self.function).next_value()
+        self.lock().next_value()
+    }
+    pub fn next_optional(&self) -> Option<Value> {
+        self.lock().next_optional()
+    }
+    pub fn try_next(&self) -> Result<Option<Value>, GenError> {
+        self.lock().try_next()
     }
 
 
     pub fn merge(&self, gen: &Generator) -> Result<Generator, GenError> {
-        RefCell::borrow_mut(&self.function)
+        self.lock()
             .merge(gen.function.clone())
             .map(|e| Generator { function: e })
     }
+
+    pub fn set_random(&self, random: Random) {
+        self.lock().set_random(random)
+    }
+
+    /// creates an independent copy of the generator to generate values in another thread.
+    pub fn fork(&self) -> Generator {
+        match self.lock().fork() {
+            Some(function) => Generator { function },
+            None => self.clone()
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, dyn GeneratorFunc + 'static> {
+        self.function.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn references(&self) -> Vec<String> {
+        self.lock().references()
+    }
+
+    pub fn resolve(&self, path: &str, value: &Value) {
+        self.lock().resolve(path, value)
+    }
+
+    /// the maximal number of items if the generator generates arrays.
+    pub fn max_len(&self) -> Option<usize> {
+        self.lock().max_len()
+    }
+
+    /// the type of the generated values if it is known beforehand.
+    pub fn value_type(&self) -> Option<ValueType> {
+        self.lock().value_type()
+    }
+
+    /// whether the generator shares the state with its copies working in other threads.
+    pub fn shares_state(&self) -> bool {
+        self.lock().shares_state()
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use crate::generator::{GeneratorFunc, Generator};
+    use crate::generator::{GeneratorFunc, Generator, Random};
     use serde_json::Value;
+    use rand::Rng;
 
     struct SimpleGenFun {}
 
@@ -98,4 +273,16 @@ mod tests {
         let f = Generator::new(SimpleGenFun {});
         if_let!(f.next() => f.next() => assert_eq!(f.next(),Value::Null))
     }
+
+    #[test]
+    fn random_test() {
+        let left = Random::from_seed(42);
+        let right = Random::from_seed(42);
+        for _ in 0..10 {
+            assert_eq!(left.with(|r| r.gen::<u64>()), right.with(|r| r.gen::<u64>()));
+        }
+
+        let shared = left.clone();
+        assert_ne!(left.with(|r| r.gen::<u64>()), shared.with(|r| r.gen::<u64>()));
+    }
 }
\ No newline at end of file
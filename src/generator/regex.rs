@@ -0,0 +1,171 @@
+use regex_syntax::hir::{Hir, HirKind, Literal, Class, ClassUnicode, ClassUnicodeRange, RepetitionKind, RepetitionRange};
+use regex_syntax::Parser;
+use rand::Rng;
+use serde_json::Value;
+use crate::generator::{GeneratorFunc, Random, Func, new_func, ValueType};
+use crate::error::GenError;
+
+/// The part of the regular expression ready to generate strings.
+#[derive(Debug, Clone)]
+enum Node {
+    /// the empty string
+    Empty,
+    /// the char from the ranges of chars
+    Chars(Vec<(char, char)>),
+    /// the node repeated from min to max times inclusively
+    Repeat(Box<Node>, u32, u32),
+    /// the nodes one after another
+    Concat(Vec<Node>),
+    /// the one of the nodes
+    Alt(Vec<Node>),
+}
+
+///The function generated random string matching the regular expression.
+/// The unbounded repetitions (`*`, `+`, `{n,}`) are capped with the maximum length of the repetition.
+/// The classes are narrowed to the printable ascii chars if they contain them (e.g. `\d`, `\w`, `.`).
+pub struct RandomRegex {
+    /// the parsed expression.
+    node: Node,
+    /// the generated random.
+    rng: Random,
+}
+
+impl RandomRegex {
+    pub fn new(pattern: &str, max_repeat: u32) -> Result<Self, GenError> {
+        let hir = Parser::new()
+            .parse(pattern)
+            .map_err(|e| GenError::new_with_in_parser(
+                format!("the regex '{}' is invalid: {}", pattern, e).as_str()))?;
+        Ok(RandomRegex { node: to_node(&hir, max_repeat)?, rng: Random::new() })
+    }
+}
+
+fn printable() -> ClassUnicode {
+    ClassUnicode::new(vec![ClassUnicodeRange::new(' ', '~')])
+}
+
+fn to_node(hir: &Hir, max_repeat: u32) -> Result<Node, GenError> {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Anchor(_) => Ok(Node::Empty),
+        HirKind::Literal(Literal::Unicode(c)) => Ok(Node::Chars(vec![(*c, *c)])),
+        HirKind::Literal(Literal::Byte(b)) if b.is_ascii() => Ok(Node::Chars(vec![(*b as char, *b as char)])),
+        HirKind::Class(Class::Unicode(cls)) => {
+            let mut ascii = cls.clone();
+            ascii.intersect(&printable());
+            let cls = if ascii.ranges().is_empty() { cls } else { &ascii };
+            if cls.ranges().is_empty() {
+                Err(GenError::new_with_in_parser("the regex class matches no chars"))
+            } else {
+                Ok(Node::Chars(cls.iter().map(|r| (r.start(), r.end())).collect()))
+            }
+        }
+        HirKind::Repetition(rep) => {
+            let (min, max) = match &rep.kind {
+                RepetitionKind::ZeroOrOne => (0, 1),
+                RepetitionKind::ZeroOrMore => (0, max_repeat),
+                RepetitionKind::OneOrMore => (1, max_repeat.max(1)),
+                RepetitionKind::Range(RepetitionRange::Exactly(n)) => (*n, *n),
+                RepetitionKind::Range(RepetitionRange::AtLeast(n)) => (*n, max_repeat.max(*n)),
+                RepetitionKind::Range(RepetitionRange::Bounded(n, m)) => (*n, *m),
+            };
+            Ok(Node::Repeat(Box::new(to_node(&rep.hir, max_repeat)?), min, max))
+        }
+        HirKind::Group(group) => to_node(&group.hir, max_repeat),
+        HirKind::Concat(hirs) =>
+            Ok(Node::Concat(hirs.iter().map(|h| to_node(h, max_repeat)).collect::<Result<_, _>>()?)),
+        HirKind::Alternation(hirs) =>
+            Ok(Node::Alt(hirs.iter().map(|h| to_node(h, max_repeat)).collect::<Result<_, _>>()?)),
+        _ => Err(GenError::new_with_in_parser(
+            format!("the regex construct '{}' is not supported", hir).as_str()))
+    }
+}
+
+fn generate<R: Rng>(node: &Node, rng: &mut R, res: &mut String) {
+    match node {
+        Node::Empty => (),
+        Node::Chars(ranges) => {
+            let total: u32 = ranges.iter().map(|(s, e)| *e as u32 - *s as u32 + 1).sum();
+            let mut idx = rng.gen_range(0, total);
+            for (s, e) in ranges.iter() {
+                let size = *e as u32 - *s as u32 + 1;
+                if idx < size {
+                    res.extend(std::char::from_u32(*s as u32 + idx));
+                    break;
+                }
+                idx -= size;
+            }
+        }
+        Node::Repeat(node, min, max) => {
+            for _ in 0..rng.gen_range(*min, *max + 1) {
+                generate(node, rng, res)
+            }
+        }
+        Node::Concat(nodes) => nodes.iter().for_each(|n| generate(n, rng, res)),
+        Node::Alt(nodes) => generate(&nodes[rng.gen_range(0, nodes.len())], rng, res),
+    }
+}
+
+impl GeneratorFunc for RandomRegex {
+    fn next_value(&mut self) -> Value {
+        let node = &self.node;
+        Value::from(self.rng.with(|r| {
+            let mut res = String::new();
+            generate(node, r, &mut res);
+            res
+        }))
+    }
+
+    fn set_random(&mut self, random: Random) {
+        self.rng = random
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        Some(ValueType::String)
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(RandomRegex { node: self.node.clone(), rng: Random::new() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::regex::RandomRegex;
+    use crate::generator::GeneratorFunc;
+    use serde_json::Value;
+
+    fn check(pattern: &str, max_repeat: u32, check: fn(&str) -> bool) {
+        let mut g = RandomRegex::new(pattern, max_repeat).unwrap();
+        for _ in 0..100 {
+            if_let!(g.next_value() => Value::String(el) => assert!(check(&el), "{} does not match {}", el, pattern));
+        }
+    }
+
+    #[test]
+    fn regex_test() {
+        check(r"[A-Z]{3}-\d{4}", 10, |s| {
+            s.len() == 8
+                && s[..3].chars().all(|c| c.is_ascii_uppercase())
+                && &s[3..4] == "-"
+                && s[4..].chars().all(|c| c.is_ascii_digit())
+        });
+        check(r"^(ab|cd)?x+$", 5, |s| {
+            let rest = s.trim_start_matches("ab").trim_start_matches("cd");
+            !rest.is_empty() && rest.len() <= 5 && rest.chars().all(|c| c == 'x')
+        });
+        check(r"\w{2,4}\.", 10, |s| {
+            (3..=5).contains(&s.len()) && s.ends_with('.')
+                && s[..s.len() - 1].chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        check(r".*", 3, |s| s.len() <= 3 && s.chars().all(|c| (' '..='~').contains(&c)));
+        check(r"[а-я]{2}", 3, |s| s.chars().count() == 2 && s.chars().all(|c| ('а'..='я').contains(&c)));
+        check(r"a{3,}", 0, |s| s == "aaa");
+    }
+
+    #[test]
+    fn regex_failed_test() {
+        if_let!(RandomRegex::new(r"\bword", 10).err() => Some(e) => assert!(e.to_string().contains("is not supported")));
+        if_let!(RandomRegex::new(r"(a", 10).err() => Some(e) => assert!(e.to_string().contains("is invalid")));
+        if_let!(RandomRegex::new(r"(?=a)", 10).err() => Some(e) => assert!(e.to_string().contains("is invalid")));
+    }
+}
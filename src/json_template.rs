@@ -1,8 +1,11 @@
-use serde_json::Value;
-use crate::generator::{Generator, GeneratorFunc};
-use crate::json_template::JsonTemplate::{Plain, Array, Object, Gen};
-use crate::parser::generators::generator;
-use crate::error::GenError;
+use std::collections::HashMap;
+use serde_json::{Map, Value};
+use crate::generator::from_string::FromStringTo;
+use crate::generator::generators::RandomArray;
+use crate::generator::{Generator, GeneratorFunc, Random, Func, new_func, ValueType, Turn};
+use crate::json_template::JsonTemplate::{Plain, Array, Object, Gen, Ordered};
+use crate::parser::generators::{generator, GeneratorRegistry, BUILT_IN};
+use crate::error::{GenError, ErrorCode};
 
 /// The common structure which carries the general notion about the generated jsons.
 /// # Example
@@ -17,6 +20,7 @@ use crate::error::GenError;
 /// }
 /// ```
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum JsonTemplate {
     /// The structure denoting the json object but enriching with the generators.
     Object(Vec<(String, JsonTemplate)>),
@@ -26,6 +30,26 @@ pub enum JsonTemplate {
     Plain(Value),
     /// The structure denoting the dynamic value. It can be a dynamic value in the field.
     Gen(Generator),
+    /// The root of the template keeping the order of the generators, see `OrderedTemplate`.
+    Ordered(OrderedTemplate),
+}
+
+/// The template along with its generators in the order of their references
+/// and the plain values the generators refer to. The order is computed once the template is created.
+#[derive(Debug)]
+pub struct OrderedTemplate {
+    template: Box<JsonTemplate>,
+    /// the generators with the paths of their fields in the order to perform them.
+    gens: Vec<(String, Generator)>,
+    /// the plain values of the fields the generators refer to.
+    plain: HashMap<String, Value>,
+}
+
+impl OrderedTemplate {
+    /// the template which has been ordered.
+    pub fn template(&self) -> &JsonTemplate {
+        &self.template
+    }
 }
 
 impl ToString for JsonTemplate {
@@ -54,12 +78,187 @@ impl ToString for JsonTemplate {
             }
             Plain(v) => v.to_string(),
             Gen(g) => g.to_string(),
+            Ordered(o) => o.template.to_string(),
+        }
+    }
+}
+
+/// the field of the directive holding the number of items: a number or a range `min..max`
+pub(crate) const REPEAT: &str = "@repeat";
+/// the field of the directive holding the template of the item
+pub(crate) const ITEM: &str = "@item";
+
+/// Parses the directive to generate the array of the items, where every item is generated by the nested template:
+/// `{"@repeat": "2..10", "@item": {...}}`
+fn parse_repeated_item(directive: &Map<String, Value>, indicator: &str, registry: &GeneratorRegistry) -> Result<Generator, GenError> {
+    let (min, max) = match directive.get(REPEAT) {
+        None => (1, 1),
+        Some(Value::Number(n)) => {
+            let len = n.as_u64().ok_or_else(|| GenError::new_with_in_parser(
+                format!("the field '{}' should be a positive number", REPEAT).as_str())
+                .with_code(ErrorCode::InvalidArguments))? as usize;
+            (len, len)
         }
+        Some(Value::String(range)) => match range.split("..").collect::<Vec<_>>()[..] {
+            [len] => {
+                let len = FromStringTo::parse(len, true)?;
+                (len, len)
+            }
+            [min, max] => (FromStringTo::parse(min, true)?, FromStringTo::parse(max, true)?),
+            _ => return Err(GenError::new_with_in_parser(
+                format!("the field '{}' should be a number or a range min..max", REPEAT).as_str())
+                .with_code(ErrorCode::InvalidArguments))
+        },
+        Some(_) => return Err(GenError::new_with_in_parser(
+            format!("the field '{}' should be a number or a range min..max", REPEAT).as_str())
+            .with_code(ErrorCode::InvalidArguments))
+    };
+    if min > max {
+        return Err(GenError::new_with_in_parser(
+            format!("the range {}..{} in the field '{}' is empty", min, max, REPEAT).as_str())
+            .with_code(ErrorCode::InvalidArguments));
     }
+    let item = directive.get(ITEM).cloned().unwrap_or(Value::Null);
+    let item = JsonTemplate::new_with_registry(item, indicator, registry).map_err(|e| e.in_field(ITEM))?;
+    Ok(Generator::new(RandomArray::new_range(min, max, Generator::new(item))))
+}
+
+/// The path of the nested field, the names of the fields are separated with dots.
+pub(crate) fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
 }
 
-fn parse_generator(gen_str: &str) -> Result<Generator, GenError> {
-    generator(gen_str)
+/// The turns of the fields among the jsons generated in parallel.
+/// The field having the generator which shares the state with its copies in other threads (e.g. `seq()`)
+/// is generated for the json only after it has been generated for the jsons with the lower indexes,
+/// therefore the json gets the same values regardless of the threads.
+#[derive(Debug)]
+pub(crate) struct FieldTurns {
+    turns: HashMap<String, Turn>,
+}
+
+impl FieldTurns {
+    pub(crate) fn new(template: &JsonTemplate) -> Self {
+        let turns = template.generators().into_iter()
+            .filter(|(_, g)| g.shares_state())
+            .map(|(path, _)| (path, Turn::new()))
+            .collect();
+        FieldTurns { turns }
+    }
+
+    /// passes the turns of the json which is not going to be generated.
+    pub(crate) fn skip(&self, idx: usize) {
+        self.turns.values().for_each(|t| t.finish(idx, || ()))
+    }
+}
+
+/// Performs the generators one by one, the generator referring to another field
+/// gets the value generated for that field before.
+/// The fields having the turns are generated in the turn of the json with the index.
+/// If a generator fails, the turns of the fields left are passed.
+/// Returns the values generated for the fields.
+fn generate_values<'a, I>(gens: I, plain: &HashMap<String, Value>, turn: Option<(usize, &FieldTurns)>)
+                          -> Result<HashMap<String, Value>, GenError>
+    where I: Iterator<Item=&'a (String, Generator)> {
+    let mut values = plain.clone();
+    let mut error = None;
+    for (path, generator) in gens {
+        let field_turn = turn.and_then(|(idx, turns)| turns.turns.get(path).map(|t| (idx, t)));
+        if error.is_some() {
+            if let Some((idx, t)) = field_turn {
+                t.finish(idx, || ())
+            }
+            continue;
+        }
+        for r in generator.references() {
+            generator.resolve(&r, lookup(&values, &r).unwrap_or(&Value::Null))
+        }
+        let next = || generator.try_next().map_err(|e| e.in_field(path));
+        let value = match field_turn {
+            Some((idx, t)) => t.finish(idx, next),
+            None => next(),
+        };
+        match value {
+            Ok(Some(v)) => { values.insert(path.clone(), v); }
+            Ok(None) => (),
+            Err(e) => error = Some(e),
+        }
+    }
+    error.map_or(Ok(values), Err)
+}
+
+/// The value of the field with the path among the values of the fields,
+/// the path can go inside the value of the field like `items.0.id` for the field `items`.
+fn lookup<'a>(values: &'a HashMap<String, Value>, path: &str) -> Option<&'a Value> {
+    if let Some(v) = values.get(path) {
+        return Some(v);
+    }
+    let (field, rest) = path.match_indices('.').rev()
+        .map(|(idx, _)| (&path[..idx], &path[idx + 1..]))
+        .find(|(field, _)| values.contains_key(*field))?;
+    rest.split('.').try_fold(values.get(field)?, |v, step| match v {
+        Value::Object(fields) => fields.get(step),
+        Value::Array(elems) => step.parse::<usize>().ok().and_then(|idx| elems.get(idx)),
+        _ => None,
+    })
+}
+
+/// The field with the generator which the path refers to: the field itself or the field
+/// which value has the path inside like `items.0.id` for the field `items`.
+fn target(paths: &HashMap<&str, usize>, path: &str) -> Option<usize> {
+    paths.get(path).copied().or_else(|| path.match_indices('.').rev()
+        .find_map(|(idx, _)| paths.get(&path[..idx]).copied()))
+}
+
+/// Sorts the generators so that every generator goes after the generators it refers to.
+/// The generator can refer to the field with a generator, to the value inside it (e.g. the items of `@repeat`)
+/// or to the plain field.
+/// Returns the indexes of the generators in the order to perform them and the plain values the generators refer to.
+fn order(template: &JsonTemplate, gens: &[(String, Generator)]) -> Result<(Vec<usize>, HashMap<String, Value>), GenError> {
+    #[derive(Clone, PartialEq)]
+    enum Mark { New, InProgress, Done }
+
+    struct Visit<'a> {
+        template: &'a JsonTemplate,
+        gens: &'a [(String, Generator)],
+        paths: HashMap<&'a str, usize>,
+        marks: Vec<Mark>,
+        res: Vec<usize>,
+        plain: HashMap<String, Value>,
+    }
+
+    fn visit(idx: usize, v: &mut Visit) -> Result<(), GenError> {
+        let gens = v.gens;
+        match v.marks[idx] {
+            Mark::Done => return Ok(()),
+            Mark::InProgress => return Err(GenError::new_with_in_parser(
+                format!("the field '{}' has a cyclic reference", gens[idx].0).as_str())
+                .with_code(ErrorCode::InvalidReference)
+                .in_field(&gens[idx].0)),
+            Mark::New => v.marks[idx] = Mark::InProgress
+        }
+        for r in gens[idx].1.references() {
+            match (target(&v.paths, r.as_str()), v.template.plain_value(&r)) {
+                (Some(next), _) => visit(next, v)?,
+                (None, Some(value)) => { v.plain.insert(r, value); }
+                (None, None) => return Err(GenError::new_with_in_parser(
+                    format!("the field '{}' refers to '{}' which is neither a field of the json nor inside a field with a generator",
+                            gens[idx].0, r).as_str())
+                    .with_code(ErrorCode::InvalidReference)
+                    .in_field(&gens[idx].0))
+            }
+        }
+        v.marks[idx] = Mark::Done;
+        v.res.push(idx);
+        Ok(())
+    }
+
+    let paths = gens.iter().enumerate().map(|(i, (p, _))| (p.as_str(), i)).collect();
+    let mut v = Visit { template, gens, paths, marks: vec![Mark::New; gens.len()], res: vec![], plain: HashMap::new() };
+    for idx in 0..gens.len() {
+        visit(idx, &mut v)?
+    }
+    Ok((v.res, v.plain))
 }
 
 impl JsonTemplate {
@@ -68,33 +267,48 @@ impl JsonTemplate {
     /// * `value` Json value represents the final json
     /// * `indicator` the prefix in the name of the field signalling the field carries the function for the generating.
     /// In the final json the indicator is removed from the field name.
+    ///
+    /// The generators referring to other fields (`ref(path)`) are checked here: the cyclic references
+    /// and the references to unknown fields are rejected.
     pub fn new(value: Value, indicator: &str) -> Result<Self, GenError> {
+        JsonTemplate::new_with_registry(value, indicator, &BUILT_IN)
+    }
+
+    /// Creates new template from the json value, the generators are resolved through the registry
+    /// which can have the user-defined generators (see `GeneratorRegistry`).
+    pub fn new_with_registry(value: Value, indicator: &str, registry: &GeneratorRegistry) -> Result<Self, GenError> {
+        JsonTemplate::build(value, indicator, "", registry)?.ordered()
+    }
+
+    fn build(value: Value, indicator: &str, path: &str, registry: &GeneratorRegistry) -> Result<Self, GenError> {
         match value {
             Value::Object(pairs) => {
                 let mut res_pairs = vec![];
-                for (k, v) in pairs.iter() {
-                    if k.starts_with(indicator) {
-                        match v {
-                            Value::String(gen_str) => {
-                                res_pairs.push((
-                                    k.strip_prefix(indicator)
-                                        .ok_or_else(|| GenError::new_with("unreachable"))?.to_string(),
-                                    Gen(parse_generator(gen_str)?)
-                                ))
-                            }
-                            _ => return Err(GenError::new_with(format!("Error for field '{}' : a generator function should be a string.", k)
-                                .as_str()))
+                for (k, v) in pairs.into_iter() {
+                    match k.strip_prefix(indicator) {
+                        Some(name) => {
+                            let field_path = child_path(path, name);
+                            let generator = match v {
+                                Value::String(gen_str) => generator(gen_str.as_str(), registry),
+                                Value::Object(directive) if directive.contains_key(ITEM) =>
+                                    parse_repeated_item(&directive, indicator, registry),
+                                _ => Err(GenError::new_with_in_parser(format!("a generator function should be a string \
+                                or an object with the fields '{}' and '{}'", REPEAT, ITEM).as_str()))
+                            };
+                            res_pairs.push((name.to_string(), Gen(generator.map_err(|e| e.in_field(&field_path))?)))
+                        }
+                        None => {
+                            let field_path = child_path(path, &k);
+                            res_pairs.push((k, JsonTemplate::build(v, indicator, &field_path, registry)?))
                         }
-                    } else {
-                        res_pairs.push((k.clone(), JsonTemplate::new(v.clone(), indicator)?))
                     }
                 }
                 Ok(Object(res_pairs))
             }
             Value::Array(elems) => {
                 let mut res_elems = vec![];
-                for e in elems.iter() {
-                    res_elems.push(JsonTemplate::new(e.clone(), indicator)?)
+                for (i, e) in elems.into_iter().enumerate() {
+                    res_elems.push(JsonTemplate::build(e, indicator, &child_path(path, &i.to_string()), registry)?)
                 }
                 Ok(Array(res_elems))
             }
@@ -104,34 +318,216 @@ impl JsonTemplate {
     /// Creates new template from the string. Due to the generators can be pointed wrongly it returns `Result`.
     /// Essentially, this method uses `JsonTemplate::new`
     pub fn from_str(json: &str, indicator: &str) -> Result<Self, GenError> {
-        let value = serde_json::from_str(json).map_err(|e| e.to_string())?;
-        JsonTemplate::new(value, indicator)
+        JsonTemplate::from_str_with_registry(json, indicator, &BUILT_IN)
+    }
+    /// Creates new template from the string, the generators are resolved through the registry
+    /// which can have the user-defined generators (see `GeneratorRegistry`).
+    pub fn from_str_with_registry(json: &str, indicator: &str, registry: &GeneratorRegistry) -> Result<Self, GenError> {
+        let value = serde_json::from_str(json)?;
+        JsonTemplate::new_with_registry(value, indicator, registry)
+    }
+    /// Sets the seed for all generators of the template.
+    /// All generators start sharing one random generator
+    /// therefore the same template with the same seed produces the same jsons.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.set_random(Random::from_seed(seed))
     }
-}
 
-impl GeneratorFunc for JsonTemplate {
-    fn next_value(&mut self) -> Value {
+    /// Creates an independent copy of the template to generate jsons in another thread.
+    /// The generators having the global state (e.g. `seq()` or `unique()`) share it with the copy.
+    pub fn fork(&self) -> JsonTemplate {
+        match self {
+            Object(pairs) => Object(pairs.iter().map(|(k, t)| (k.clone(), t.fork())).collect()),
+            Array(elems) => Array(elems.iter().map(|t| t.fork()).collect()),
+            Plain(v) => Plain(v.clone()),
+            Gen(g) => Gen(g.fork()),
+            Ordered(o) => {
+                let template = o.template.fork();
+                let mut forked: HashMap<String, Generator> = template.generators().into_iter().collect();
+                let gens = o.gens.iter().filter_map(|(p, _)| forked.remove(p).map(|g| (p.clone(), g))).collect();
+                Ordered(OrderedTemplate { template: Box::new(template), gens, plain: o.plain.clone() })
+            }
+        }
+    }
+
+    /// Wraps the template into the root keeping the generators in the order of their references.
+    /// The cyclic references and the references to unknown fields are rejected.
+    fn ordered(self) -> Result<Self, GenError> {
+        let gens = self.generators();
+        let (indexes, plain) = order(&self, &gens)?;
+        let gens = indexes.into_iter().map(|idx| gens[idx].clone()).collect();
+        Ok(Ordered(OrderedTemplate { template: Box::new(self), gens, plain }))
+    }
+
+    /// Returns the plain value of the field with the path, the field should have no generators inside.
+    fn plain_value(&self, path: &str) -> Option<Value> {
+        let mut template = self;
+        for step in path.split('.') {
+            template = match template {
+                Object(pairs) => pairs.iter().find(|(k, _)| k == step).map(|(_, t)| t)?,
+                Array(elems) => step.parse::<usize>().ok().and_then(|idx| elems.get(idx))?,
+                Ordered(o) => return o.template.plain_value(path),
+                _ => return None,
+            }
+        }
+        if template.generators().is_empty() {
+            Some(template.compose(path, &mut HashMap::new()))
+        } else {
+            None
+        }
+    }
+
+    /// Generates the json having the index among the jsons generated in parallel,
+    /// the fields having the turns are generated after the jsons with the lower indexes.
+    pub(crate) fn try_next_in_turn(&mut self, idx: usize, turns: &FieldTurns) -> Result<Option<Value>, GenError> {
+        self.generate(Some((idx, turns)))
+    }
+
+    fn generate(&mut self, turn: Option<(usize, &FieldTurns)>) -> Result<Option<Value>, GenError> {
+        let mut values = match self {
+            Ordered(o) => generate_values(o.gens.iter(), &o.plain, turn)?,
+            _ => {
+                let gens = self.generators();
+                let (indexes, plain) = order(self, &gens)?;
+                generate_values(indexes.into_iter().map(|idx| &gens[idx]), &plain, turn)?
+            }
+        };
+        Ok(Some(self.compose("", &mut values)))
+    }
+
+    /// Returns the lazy endless iterator generating new jsons one by one.
+    /// The iterator stops after the first error.
+    pub fn iter(&mut self) -> JsonIter<'_> {
+        JsonIter { template: self, failed: false }
+    }
+
+    /// Returns the generators of the template along with the paths of their fields.
+    fn generators(&self) -> Vec<(String, Generator)> {
+        fn collect(template: &JsonTemplate, path: String, res: &mut Vec<(String, Generator)>) {
+            match template {
+                Object(pairs) =>
+                    pairs.iter().for_each(|(k, t)| collect(t, child_path(&path, k), res)),
+                Array(elems) =>
+                    elems.iter().enumerate().for_each(|(i, t)| collect(t, child_path(&path, &i.to_string()), res)),
+                Plain(_) => (),
+                Gen(g) => res.push((path, g.clone())),
+                Ordered(o) => collect(&o.template, path, res),
+            }
+        }
+        let mut res = vec![];
+        collect(self, String::new(), &mut res);
+        res
+    }
+
+    /// Composes the json, taking the values generated for the fields.
+    fn compose(&self, path: &str, values: &mut HashMap<String, Value>) -> Value {
         match self {
             Object(gen_pairs) => {
                 let mut fields = serde_json::Map::new();
-                for (k, t) in gen_pairs.iter_mut() {
-                    fields.insert(k.clone(), t.next_value());
+                for (k, t) in gen_pairs.iter() {
+                    let field_path = child_path(path, k);
+                    match t {
+                        Gen(_) => if let Some(v) = values.remove(&field_path) {
+                            fields.insert(k.clone(), v);
+                        },
+                        _ => { fields.insert(k.clone(), t.compose(&field_path, values)); }
+                    }
                 }
                 Value::from(fields)
             }
-
             Array(elems) =>
-                Value::Array(elems.iter_mut().map(|t| t.next_value()).collect()),
+                Value::Array(elems.iter().enumerate().map(|(i, t)| t.compose(&child_path(path, &i.to_string()), values)).collect()),
             Plain(v) => v.clone(),
-            Gen(generator) => generator.next()
+            Gen(_) => values.remove(path).unwrap_or(Value::Null),
+            Ordered(o) => o.template.compose(path, values),
         }
     }
 }
 
+/// The lazy iterator generating new jsons from the template one by one.
+/// It is created by `JsonTemplate::iter`.
+pub struct JsonIter<'a> {
+    template: &'a mut JsonTemplate,
+    /// the flag signalling the generating has failed and the iterator is over.
+    failed: bool,
+}
+
+impl<'a> Iterator for JsonIter<'a> {
+    type Item = Result<Value, GenError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.template.try_next() {
+            Ok(v) => Some(Ok(v.unwrap_or(Value::Null))),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl GeneratorFunc for JsonTemplate {
+    /// Generates the json, the error is logged and turned into null.
+    fn next_value(&mut self) -> Value {
+        self.try_next()
+            .unwrap_or_else(|e| {
+                error!("generating json, error : {}", e);
+                None
+            })
+            .unwrap_or(Value::Null)
+    }
+
+    /// Performs the generators in the order of their references,
+    /// so the generator referring to another field gets the value generated for that field.
+    /// The fields which generators produce nothing are omitted (the referring generators get null).
+    /// The template created not through `JsonTemplate::new` gets the order of the generators every time.
+    fn try_next(&mut self) -> Result<Option<Value>, GenError> {
+        self.generate(None)
+    }
+
+    fn set_random(&mut self, random: Random) {
+        match self {
+            Object(gen_pairs) =>
+                gen_pairs.iter_mut().for_each(|(_, t)| t.set_random(random.clone())),
+            Array(elems) =>
+                elems.iter_mut().for_each(|t| t.set_random(random.clone())),
+            Plain(_) => (),
+            Gen(generator) => generator.set_random(random),
+            Ordered(o) => o.template.set_random(random),
+        }
+    }
+
+    fn value_type(&self) -> Option<ValueType> {
+        match self {
+            Object(_) => Some(ValueType::Object),
+            Array(_) => Some(ValueType::Array),
+            Plain(v) => ValueType::of(v),
+            Gen(g) => g.value_type(),
+            Ordered(o) => o.template.value_type(),
+        }
+    }
+
+    fn shares_state(&self) -> bool {
+        self.generators().iter().any(|(_, g)| g.shares_state())
+    }
+
+    fn fork(&self) -> Option<Func> {
+        Some(new_func(JsonTemplate::fork(self)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::json_template::JsonTemplate;
-    use serde_json::json;
+    use crate::error::{GenError, ErrorCode};
+    use std::error::Error;
+    use crate::generator::{GeneratorFunc, GeneratorRegistry, Generator};
+    use crate::generator::generators::RandomFromList;
+    use crate::parser::generators::generator;
+    use serde_json::{json, Value};
 
     #[test]
     fn simple_test() {
@@ -147,11 +543,16 @@ mod tests {
     #[test]
     fn simple_failed_test() {
         let json = json!({
-            "|field": "uuidds()",
+            "record": {"|field": "uuidds()"},
             "num" : 1
         });
-        let res = JsonTemplate::new(json, "|");
-        println!("{}", res.err().unwrap());
+        let e = JsonTemplate::new(json, "|").err().unwrap();
+        assert_eq!((e.path(), e.generator(), e.code()), (Some("record.field"), Some("uuidds"), ErrorCode::UnknownGenerator));
+        assert_eq!((e.expression(), e.span()), (Some("uuidds()"), Some(0..6)));
+
+        let e = JsonTemplate::from_str(r#"{"|a": "#, "|").err().unwrap();
+        assert_eq!(e.code(), ErrorCode::InvalidJson);
+        assert!(e.source().is_some());
     }
 
     #[test]
@@ -162,4 +563,235 @@ mod tests {
         assert!(res.is_ok());
         println!("{}", res.unwrap().to_string());
     }
+
+    #[test]
+    fn seed_test() {
+        let body = r#"{"|id": "uuid()","|name": "str(10)","|tags": "str_from_list(a,b,c) -> array(3)","|num": "int(1,1000)"}"#;
+        let mut left = JsonTemplate::from_str(body, "|").unwrap();
+        let mut right = JsonTemplate::from_str(body, "|").unwrap();
+        left.set_seed(7);
+        right.set_seed(7);
+        for _ in 0..10 {
+            assert_eq!(left.next_value(), right.next_value());
+        }
+    }
+
+    #[test]
+    fn reference_test() {
+        let mut template = JsonTemplate::from_str(r#"
+        {
+            "|self_link": "ref(record.technical.id)",
+            "record": {
+                "|ids": "ref(record.technical.id) -> array(2)",
+                "technical": { "|id": "uuid()", "|index": "seq()" },
+                "|index": "ref(record.technical.index)"
+            },
+            "items": [{"|id": "seq(10)"}, {"|parent": "ref(items.0.id)"}]
+        }"#, "|").unwrap();
+
+        for _ in 0..3 {
+            let json = template.next_value();
+            let id = json.pointer("/record/technical/id").unwrap();
+            assert!(id.is_string());
+            assert_eq!(json.get("self_link"), Some(id));
+            assert_eq!(json.pointer("/record/ids"), Some(&json!([id, id])));
+            assert_eq!(json.pointer("/record/index"), json.pointer("/record/technical/index"));
+            assert_eq!(json.pointer("/items/1/parent"), json.pointer("/items/0/id"));
+        }
+    }
+
+    #[test]
+    fn reference_failed_test() {
+        let res = JsonTemplate::from_str(r#"{"|a": "ref(b)", "|b": "ref(c)", "|c": "ref(a)"}"#, "|");
+        assert!(res.err().unwrap().to_string().contains("cyclic reference"));
+
+        let res = JsonTemplate::from_str(r#"{"|a": "ref(a)"}"#, "|");
+        assert!(res.err().unwrap().to_string().contains("cyclic reference"));
+
+        let res = JsonTemplate::from_str(r#"{"|a": "ref(b)", "c": 1}"#, "|");
+        let e = res.err().unwrap();
+        assert!(e.to_string().contains("refers to 'b'"));
+        assert_eq!((e.path(), e.code()), (Some("a"), ErrorCode::InvalidReference));
+
+        // the generators inside the item of @repeat refer to the fields of the item
+        let res = JsonTemplate::from_str(r#"{"|id": "seq()", "|lines": {"@repeat": 2, "@item": {"|parent": "ref(id)"}}}"#, "|");
+        let e = res.err().unwrap();
+        assert_eq!((e.path(), e.code()), (Some("lines.@item.parent"), ErrorCode::InvalidReference));
+
+        // the plain field having generators inside can not be referred to as a whole
+        let res = JsonTemplate::from_str(r#"{"a": {"|b": "int()"}, "|c": "ref(a)"}"#, "|");
+        assert_eq!(res.err().unwrap().path(), Some("c"));
+
+        let registry = GeneratorRegistry::default();
+        let mut template = JsonTemplate::Object(vec![
+            ("a".to_string(), JsonTemplate::Gen(generator("ref(b)", &registry).unwrap())),
+            ("b".to_string(), JsonTemplate::Gen(generator("ref(a)", &registry).unwrap())),
+        ]);
+        if_let!(template.try_next() => Err(e) => assert_eq!(e.code(), ErrorCode::InvalidReference));
+    }
+
+    #[test]
+    fn reference_paths_test() {
+        let mut template = JsonTemplate::from_str(r#"
+        {
+            "version": 3,
+            "meta": {"tags": ["a", "b"], "kind": "order"},
+            "|lines": {"@repeat": 2, "@item": {"|id": "seq()"}},
+            "|version_ref": "ref(version)",
+            "|tag": "ref(meta.tags.1)",
+            "|meta_ref": "ref(meta)",
+            "|first": "ref(lines.0.id)",
+            "|link": "fmt(/lines/{}, ref(lines.1.id))",
+            "|missing": "ref(lines.5.id)"
+        }"#, "|").unwrap();
+
+        for _ in 0..3 {
+            let json = template.next_value();
+            assert_eq!(json["version_ref"], json!(3));
+            assert_eq!(json["tag"], json!("b"));
+            assert_eq!(json["meta_ref"], json!({"tags": ["a", "b"], "kind": "order"}));
+            assert_eq!(json.pointer("/first"), json.pointer("/lines/0/id"));
+            assert_eq!(json["link"], json!(format!("/lines/{}", json["lines"][1]["id"])));
+            assert_eq!(json["missing"], Value::Null);
+        }
+    }
+
+    #[test]
+    fn ordered_fork_test() {
+        let template = JsonTemplate::from_str(r#"{"|b": "ref(a)", "|a": "seq()", "c": {"|d": "ref(a)"}}"#, "|").unwrap();
+        let mut forked = template.fork();
+        if_let!(&forked => JsonTemplate::Ordered(o)
+            => assert_eq!(o.gens.iter().map(|(p, _)| p.as_str()).collect::<Vec<&str>>(), vec!["a", "c.d", "b"]));
+        assert_eq!(forked.next_value(), json!({"a": 1, "b": 1, "c": {"d": 1}}));
+    }
+
+    #[test]
+    fn repeated_item_test() {
+        let mut template = JsonTemplate::from_str(r#"
+        {
+            "|lines": {
+                "@repeat": "2..4",
+                "@item": { "|id": "seq()", "|price": "float(1,10)", "|link": "fmt(/lines/{}, ref(id))", "currency": "EUR" }
+            },
+            "|addresses": { "@repeat": 2, "@item": { "|city": "str_from_list(Rome,Berlin)" } },
+            "|single": { "@item": { "|id": "uuid()" } }
+        }"#, "|").unwrap();
+
+        let mut ids = vec![];
+        for _ in 0..10 {
+            let json = template.next_value();
+            if_let!(json.get("lines") => Some(Value::Array(lines)) => {
+                assert!((2..=4).contains(&lines.len()));
+                for line in lines.iter() {
+                    let id = line.get("id").and_then(|id| id.as_i64()).unwrap();
+                    assert_eq!(line.get("link"), Some(&json!(format!("/lines/{}", id))));
+                    assert_eq!(line.get("currency"), Some(&json!("EUR")));
+                    ids.push(id);
+                }
+            });
+            if_let!(json.get("addresses") => Some(Value::Array(addresses)) => assert_eq!(addresses.len(), 2));
+            if_let!(json.get("single") => Some(Value::Array(items)) => assert_eq!(items.len(), 1));
+        }
+        assert_eq!(ids, (1..=ids.len() as i64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn repeated_item_failed_test() {
+        let res = JsonTemplate::from_str(r#"{"|lines": {"@repeat": "4..2", "@item": {}}}"#, "|");
+        assert!(res.err().unwrap().to_string().contains("is empty"));
+        let res = JsonTemplate::from_str(r#"{"|lines": {"@repeat": "a..b", "@item": {}}}"#, "|");
+        assert!(res.err().unwrap().to_string().contains("lines"));
+        let res = JsonTemplate::from_str(r#"{"|lines": {"@repeat": 2}}"#, "|");
+        assert!(res.err().unwrap().to_string().contains("should be a string or an object"));
+        let res = JsonTemplate::from_str(r#"{"|lines": {"@repeat": 2, "@item": {"tags": ["a", {"|b": "int(2,1) -> arr()"}]}}}"#, "|");
+        assert_eq!(res.err().unwrap().path(), Some("lines.@item.tags.1.b"));
+    }
+
+    #[test]
+    fn optional_test() {
+        let mut template = JsonTemplate::from_str(r#"
+        {
+            "|always": "str(5) -> optional(0)",
+            "|never": "str(5) -> optional(1)",
+            "|null": "str(5) -> nullable(1)",
+            "|never_ref": "ref(never)",
+            "|sometimes": "int(1,10) -> optional(0.5)",
+            "nested": [{"|never": "int() -> optional(1)"}]
+        }"#, "|").unwrap();
+
+        let mut omitted = false;
+        for _ in 0..100 {
+            let json = template.next_value();
+            assert!(json.get("always").unwrap().is_string());
+            assert_eq!(json.get("never"), None);
+            assert_eq!(json.get("null"), Some(&Value::Null));
+            assert_eq!(json.get("never_ref"), Some(&Value::Null));
+            assert_eq!(json.get("nested"), Some(&json!([{}])));
+            omitted |= json.get("sometimes").is_none();
+        }
+        assert!(omitted);
+    }
+
+    #[test]
+    fn fork_test() {
+        fn check_send<T: Send + Sync>(_: &T) {}
+
+        let mut template = JsonTemplate::from_str(r#"{"|id": "seq()", "|num": "int(1,1000)", "|ids": "ref(id) -> array(2)"}"#, "|").unwrap();
+        let mut forked = template.fork();
+        check_send(&forked);
+        template.set_seed(1);
+        forked.set_seed(1);
+
+        let first = template.next_value();
+        let second = forked.next_value();
+        assert_eq!(first["id"], json!(1));
+        assert_eq!(second["id"], json!(2));
+        assert_eq!(second["ids"], json!([2, 2]));
+        assert_eq!(first["num"], second["num"]);
+        assert_eq!(std::thread::spawn(move || forked.next_value()["id"].clone()).join().unwrap(), json!(3));
+    }
+
+    #[test]
+    fn iter_test() {
+        let mut template = JsonTemplate::from_str(r#"{"|id": "seq()", "|code": "int(1,3) -> unique()"}"#, "|").unwrap();
+        let values: Vec<Result<Value, GenError>> = template.iter().take(5).collect();
+        assert_eq!(values.len(), 3);
+        if_let!(&values[0] => Ok(v) => assert_eq!(v.get("id"), Some(&json!(1))));
+        if_let!(&values[1] => Ok(v) => assert_eq!(v.get("id"), Some(&json!(2))));
+        if_let!(&values[2] => Err(e) => assert!(e.to_string().contains("exhausted")));
+
+        let mut template = JsonTemplate::from_str(r#"{"|id": "seq()"}"#, "|").unwrap();
+        let ids: Vec<Value> = template.iter().skip(999).take(2).map(|v| v.unwrap()["id"].clone()).collect();
+        assert_eq!(ids, vec![json!(1000), json!(1001)]);
+    }
+
+    #[test]
+    fn unique_test() {
+        let mut template = JsonTemplate::from_str(r#"
+        {
+            "|id": "int(1,4) -> unique()",
+            "|codes": "str_from_list(a,b,c,d,e,f,g,h) -> unique(100) -> array(2)"
+        }"#, "|").unwrap();
+
+        let mut ids = vec![];
+        for _ in 0..3 {
+            if_let!(template.try_next() => Ok(Some(json)) => ids.push(json.get("id").unwrap().as_i64().unwrap()));
+        }
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2, 3]);
+        if_let!(template.try_next() => Err(e) => assert!(e.to_string().contains("the unique values are exhausted")));
+    }
+
+    #[test]
+    fn registry_test() {
+        let mut registry = GeneratorRegistry::default();
+        registry.register("status", |args| Ok(Generator::new(RandomFromList::new(
+            args.into_iter().map(|a| a.to_string()).collect::<Vec<String>>()))));
+        let json = r#"{"|status": "status(active)", "|items": {"@repeat": 2, "@item": {"|s": "status(new)"}}}"#;
+        let mut template = JsonTemplate::from_str_with_registry(json, "|", &registry).unwrap();
+        assert_eq!(template.next_value(), json!({"status": "active", "items": [{"s": "new"}, {"s": "new"}]}));
+
+        if_let!(JsonTemplate::from_str(json, "|") => Err(e)
+            => assert_eq!((e.path(), e.code()), (Some("items.@item.s"), ErrorCode::UnknownGenerator)));
+    }
 }
@@ -56,9 +56,14 @@
 
 
 use serde_json::Value;
-use crate::generator::GeneratorFunc;
-use crate::json_template::JsonTemplate;
-use crate::sender::Sender;
+use crate::json_template::{JsonTemplate, JsonIter, FieldTurns};
+use crate::sender::{Sender, Summary, BatchResult};
+use crate::error::GenError;
+use crate::generator::Turn;
+use crate::pacing::Pacing;
+use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
 
 #[macro_use]
 pub extern crate log;
@@ -71,7 +76,9 @@ mod parser;
 pub mod generator;
 pub mod sender;
 pub mod json_template;
-mod error;
+pub mod error;
+pub mod pacing;
+pub mod validation;
 
 /// the top level function to generate new json.
 /// # Arguments
@@ -80,6 +87,11 @@ mod error;
 /// * `pretty` : flag indicating whether need to make the json readable
 /// * `outputs` : the list of Senders
 ///
+/// The senders are started before the generation and finished after it (see `Sender::start` and `Sender::finish`).
+/// The senders expecting batches (see `Sender::batch_size`) get the jsons in batches,
+/// the last batch can be incomplete.
+/// If a generator fails (e.g. the unique values are exhausted), the error is logged
+/// and the jsons generated before are returned.
 /// # Examples
 /// ```rust
 /// use json_gen::json_template::JsonTemplate;
@@ -95,24 +107,277 @@ pub fn generate(json: &mut JsonTemplate,
                 rep: usize,
                 pretty: bool,
                 outputs: &mut Vec<Box<dyn Sender>>) -> Vec<Value> {
-    debug!("generate the {} repetitions. ", rep);
     let mut res = vec![];
-    for _ in 0..rep {
-        let value = json.next_value();
-        res.push(value.clone());
-        for v in outputs.iter_mut() {
-            match v.send(&value, pretty) {
-                Ok(res) => info!("sending json, success : {}", res),
-                Err(e) => error!("sending json, error : {}", e)
+    let mut outputs = match Outputs::start(outputs, pretty) {
+        Ok(outputs) => outputs,
+        Err(e) => {
+            error!("starting the senders, error : {}", e);
+            return res;
+        }
+    };
+    for value in generate_iter(json).take(rep) {
+        match value {
+            Ok(v) => {
+                outputs.send(&v);
+                res.push(v)
             }
+            Err(e) => error!("generating json, error : {}", e)
         }
     }
+    drop(outputs);
     res
 }
 
+/// the same as `generate` but it stops with the error if a generator fails.
+pub fn try_generate(json: &mut JsonTemplate,
+                    rep: usize,
+                    pretty: bool,
+                    outputs: &mut Vec<Box<dyn Sender>>) -> Result<Vec<Value>, GenError> {
+    let mut outputs = Outputs::start(outputs, pretty)?;
+    let values = generate_iter(json)
+        .take(rep)
+        .map(|v| v.inspect(|v| outputs.send(v)))
+        .collect::<Result<Vec<Value>, GenError>>()?;
+    outputs.finish()?;
+    Ok(values)
+}
+
+/// generates the jsons and sends them to the outputs one by one without keeping them in memory.
+/// Returns the number of the generated jsons or the error if a generator fails.
+pub fn generate_to(json: &mut JsonTemplate,
+                   rep: usize,
+                   pretty: bool,
+                   outputs: &mut Vec<Box<dyn Sender>>) -> Result<usize, GenError> {
+    generate_paced(json, &Pacing::default().with_count(rep), pretty, outputs)
+}
+
+/// generates the jsons with the rate and within the bounds of the pacing
+/// and sends them to the outputs one by one without keeping them in memory.
+/// Returns the number of the generated jsons or the error if a generator fails.
+/// # Examples
+/// ```rust
+/// use json_gen::json_template::JsonTemplate;
+/// use json_gen::generate_paced;
+/// use json_gen::pacing::{Pacing, Rate};
+/// use std::time::Duration;
+///
+///     let mut json_template = JsonTemplate::from_str("{\"|id\":\"seq()\"}", "|").unwrap();
+///     let pacing = Pacing::default()
+///         .with_duration(Duration::from_millis(100))
+///         .with_rate(Rate::parse("200/s", None).unwrap());
+///     let count = generate_paced(&mut json_template, &pacing, false, &mut vec![]).unwrap();
+///     assert!(count <= 20);
+/// ```
+pub fn generate_paced(json: &mut JsonTemplate,
+                      pacing: &Pacing,
+                      pretty: bool,
+                      outputs: &mut Vec<Box<dyn Sender>>) -> Result<usize, GenError> {
+    let mut count = 0;
+    let mut outputs = Outputs::start(outputs, pretty)?;
+    let pacer = pacing.start();
+    let mut values = generate_iter(json);
+    while pacer.wait(count) {
+        match values.next() {
+            Some(value) => outputs.send(&value?),
+            None => break,
+        }
+        count += 1;
+    }
+    outputs.finish()?;
+    Ok(count)
+}
+
+/// the lazy endless iterator generating new jsons one by one.
+/// # Examples
+/// ```rust
+/// use json_gen::json_template::JsonTemplate;
+/// use json_gen::generate_iter;
+///
+///     let mut json_template = JsonTemplate::from_str("{\"|id\":\"seq()\"}", "|").unwrap();
+///     for value in generate_iter(&mut json_template).take(1_000) {
+///         println!("{}", value.unwrap());
+///     }
+/// ```
+pub fn generate_iter(json: &mut JsonTemplate) -> JsonIter<'_> {
+    debug!("generate the jsons lazily.");
+    json.iter()
+}
+
+/// the number of the generated jsons per thread which can wait for the senders.
+pub const QUEUE_PER_THREAD: usize = 4;
+
+/// generates the jsons in parallel and sends them to the outputs one by one in the order of generating.
+/// Returns the number of the generated jsons or the error if a generator fails.
+/// # Arguments
+/// * `json` : JsonTemplate having the functions to generate dynamic fields
+/// * `pacing` : the bounds and the rate of the generation (see `generate_paced`)
+/// * `threads` : the number of workers sharing the repetitions, greater than zero
+/// * `seed` : the seed to get the same jsons every run
+/// * `pretty` : flag indicating whether need to make the json readable
+/// * `outputs` : the list of Senders
+///
+/// Every worker generates the jsons with its own copy of the template (see `JsonTemplate::fork`).
+/// The fields having the generators with the global state (e.g. `seq()`, `unique()`) take turns:
+/// the field is generated for the json after the jsons with the lower indexes,
+/// so the jsons get the values in the same order as if they were generated in one thread.
+/// Being seeded, every json gets the random generator seeded with the seed and the index of the json,
+/// therefore the jsons are the same every run regardless of the number of threads.
+/// Note: that differs from `JsonTemplate::set_seed` used with `generate` or `generate_paced`
+/// where all jsons draw from one random generator, so the same seed gives other jsons there.
+/// The generated jsons wait for the senders in the queue bounded by `QUEUE_PER_THREAD` jsons per thread,
+/// so the workers are held back by the slow senders.
+pub fn generate_par(json: &JsonTemplate,
+                    pacing: &Pacing,
+                    threads: usize,
+                    seed: Option<u64>,
+                    pretty: bool,
+                    outputs: &mut Vec<Box<dyn Sender>>) -> Result<usize, GenError> {
+    debug!("generate the jsons in {} threads. ", threads);
+    if threads == 0 {
+        return Err(GenError::new_with("the number of threads should be greater than zero"));
+    }
+    let mut outputs = Outputs::start(outputs, pretty)?;
+    let pacer = pacing.start();
+    let turn = Turn::new();
+    let field_turns = FieldTurns::new(json);
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = mpsc::sync_channel(threads * QUEUE_PER_THREAD);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let (mut template, tx, turn, field_turns, next, stop, pacer) =
+                (json.fork(), tx.clone(), &turn, &field_turns, &next, &stop, &pacer);
+            scope.spawn(move || loop {
+                let idx = next.fetch_add(1, Ordering::SeqCst);
+                let due = pacer.wait(idx);
+                let value = if !due || stop.load(Ordering::SeqCst) {
+                    field_turns.skip(idx);
+                    None
+                } else {
+                    if let Some(seed) = seed {
+                        template.set_seed(seed ^ (idx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+                    }
+                    Some(template.try_next_in_turn(idx, field_turns).map(|v| v.unwrap_or(Value::Null)))
+                };
+                let done = value.is_none();
+                turn.finish(idx, || value.map(|v| tx.send(v)));
+                if done {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        let mut count = 0;
+        for value in rx {
+            match value {
+                Ok(v) => {
+                    outputs.send(&v);
+                    count += 1
+                }
+                Err(e) => {
+                    stop.store(true, Ordering::SeqCst);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(count)
+    }).and_then(|count| outputs.finish().map(|_| count))
+}
+
+/// passes the generated jsons to the senders,
+/// keeping them for the senders expecting batches until the batch is full.
+/// The senders are started when the outputs are created.
+/// The incomplete batches are sent and the senders are finished with `finish`
+/// or when the outputs are dropped (e.g. the generation has failed).
+struct Outputs<'a> {
+    senders: &'a mut [Box<dyn Sender>],
+    /// the jsons kept for the sender and the totals counted from the results of sending.
+    states: Vec<(Vec<Value>, Summary)>,
+    pretty: bool,
+    finished: bool,
+}
+
+impl<'a> Outputs<'a> {
+    fn start(senders: &'a mut [Box<dyn Sender>], pretty: bool) -> Result<Self, GenError> {
+        for sender in senders.iter_mut() {
+            sender.start()?;
+        }
+        let states = senders.iter().map(|_| (vec![], Summary::default())).collect();
+        Ok(Outputs { senders, states, pretty, finished: false })
+    }
+
+    fn send(&mut self, value: &Value) {
+        for (sender, (batch, summary)) in self.senders.iter_mut().zip(self.states.iter_mut()) {
+            let size = sender.batch_size();
+            if size > 1 {
+                batch.push(value.clone());
+                if batch.len() >= size {
+                    count_sent(sender.send_batch(batch, self.pretty), batch.len(), summary);
+                    batch.clear();
+                }
+            } else {
+                count_sent(BatchResult::all(1, sender.send(value, self.pretty)), 1, summary)
+            }
+        }
+    }
+
+    /// sends the incomplete batches and finishes the senders returning the totals of every sender.
+    /// The totals counted from the results of sending are taken for the senders reporting the empty totals.
+    fn finish(mut self) -> Result<Vec<Summary>, GenError> {
+        self.close().into_iter().collect()
+    }
+
+    fn close(&mut self) -> Vec<Result<Summary, GenError>> {
+        self.finished = true;
+        let pretty = self.pretty;
+        self.senders.iter_mut().zip(self.states.iter_mut()).map(|(sender, (batch, summary))| {
+            if !batch.is_empty() {
+                count_sent(sender.send_batch(batch, pretty), batch.len(), summary);
+                batch.clear();
+            }
+            let res = sender.finish().map(|s| if s == Summary::default() { summary.clone() } else { s });
+            match &res {
+                Ok(s) => info!("finishing the sender, success : {}", s),
+                Err(e) => error!("finishing the sender, error : {}", e)
+            }
+            res
+        }).collect()
+    }
+}
+
+impl<'a> Drop for Outputs<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.close();
+        }
+    }
+}
+
+/// counts the jsons of the batch which have been sent and which have failed.
+fn count_sent(res: BatchResult, items: usize, summary: &mut Summary) {
+    summary.sent += res.sent;
+    summary.failed += items.saturating_sub(res.sent);
+    log_sent(res.result)
+}
+
+fn log_sent(res: Result<String, GenError>) {
+    match res {
+        Ok(res) => info!("sending json, success : {}", res),
+        Err(e) => error!("sending json, error : {}", e)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::generate;
+    use crate::{generate, try_generate, generate_par, generate_to, generate_paced, generate_iter, Outputs};
+    use crate::sender::{Summary, BatchResult};
+    use crate::pacing::{Pacing, Rate};
+    use std::time::{Duration, Instant};
+    use crate::sender::Sender;
+    use crate::error::GenError;
+    use std::sync::{Arc, Mutex};
     use crate::json_template::JsonTemplate;
     use serde_json::Value;
     use chrono::Utc;
@@ -127,6 +392,228 @@ mod tests {
                 => assert!(map.get("id").and_then(|e|e.as_str()).unwrap().len() == 36 ));
     }
 
+    #[test]
+    fn unique_test() {
+        let jt_body = r#"{"|id": "int(1,4) -> unique()"}"#;
+        let mut js_template = JsonTemplate::from_str(jt_body, "|").unwrap();
+        assert_eq!(generate(&mut js_template, 5, false, &mut vec![]).len(), 3);
+
+        let mut js_template = JsonTemplate::from_str(jt_body, "|").unwrap();
+        if_let!(try_generate(&mut js_template, 5, false, &mut vec![])
+            => Err(e) => assert!(e.to_string().contains("the unique values are exhausted")));
+    }
+
+    /// keeps the sent jsons to check them.
+    struct VecSender {
+        values: Arc<Mutex<Vec<Value>>>
+    }
+
+    impl Sender for VecSender {
+        fn send(&mut self, json: &Value, _pretty: bool) -> Result<String, GenError> {
+            self.values.lock().unwrap().push(json.clone());
+            Ok("the item has been kept".to_string())
+        }
+    }
+
+    /// keeps the sizes of the sent batches.
+    struct BatchSender {
+        batches: Arc<Mutex<Vec<usize>>>
+    }
+
+    impl Sender for BatchSender {
+        fn send(&mut self, _json: &Value, _pretty: bool) -> Result<String, GenError> {
+            self.batches.lock().unwrap().push(1);
+            Ok("the item has been counted".to_string())
+        }
+
+        fn send_batch(&mut self, jsons: &[Value], _pretty: bool) -> BatchResult {
+            self.batches.lock().unwrap().push(jsons.len());
+            BatchResult::all(jsons.len(), Ok("the batch has been counted".to_string()))
+        }
+
+        fn batch_size(&self) -> usize {
+            3
+        }
+    }
+
+    #[test]
+    fn batch_test() {
+        let js_template = JsonTemplate::from_str(r#"{"|id": "seq()"}"#, "|").unwrap();
+        let values = Arc::new(Mutex::new(vec![]));
+        let batches = Arc::new(Mutex::new(vec![]));
+        let mut outputs: Vec<Box<dyn Sender>> = vec![
+            Box::new(VecSender { values: values.clone() }),
+            Box::new(BatchSender { batches: batches.clone() }),
+        ];
+        assert_eq!(generate_to(&mut js_template.fork(), 7, false, &mut outputs).unwrap(), 7);
+        assert_eq!(values.lock().unwrap().len(), 7);
+        assert_eq!(*batches.lock().unwrap(), vec![3, 3, 1]);
+
+        batches.lock().unwrap().clear();
+        assert_eq!(generate_par(&js_template, &Pacing::default().with_count(6), 2, None, false, &mut outputs).unwrap(), 6);
+        assert_eq!(*batches.lock().unwrap(), vec![3, 3]);
+    }
+
+    #[test]
+    fn generate_paced_test() {
+        let js_template = JsonTemplate::from_str(r#"{"|id": "seq()"}"#, "|").unwrap();
+        let values = Arc::new(Mutex::new(vec![]));
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(VecSender { values: values.clone() })];
+
+        let start = Instant::now();
+        let pacing = Pacing::default().with_count(10).with_rate(Rate::Fixed(100.0));
+        assert_eq!(generate_paced(&mut js_template.fork(), &pacing, false, &mut outputs).unwrap(), 10);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+
+        let pacing = Pacing::default().with_duration(Duration::from_millis(200)).with_rate(Rate::Fixed(50.0));
+        assert_eq!(generate_paced(&mut js_template.fork(), &pacing, false, &mut outputs).unwrap(), 10);
+        let count = generate_par(&js_template, &pacing, 4, None, false, &mut outputs).unwrap();
+        assert_eq!(count, 10);
+        let ids: Vec<i64> = values.lock().unwrap().iter().skip(20).map(|v| v["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, (21..=30).collect::<Vec<i64>>());
+    }
+
+    /// keeps the calls of the lifecycle hooks.
+    struct LifecycleSender {
+        events: Arc<Mutex<Vec<String>>>,
+        summary: Summary,
+    }
+
+    impl Sender for LifecycleSender {
+        fn send(&mut self, json: &Value, _pretty: bool) -> Result<String, GenError> {
+            self.events.lock().unwrap().push(json["id"].to_string());
+            if json["id"] == 2 { Err(GenError::new_with_in_sender("the item 2 is failed")) } else { Ok("the item has been kept".to_string()) }
+        }
+
+        fn start(&mut self) -> Result<(), GenError> {
+            self.events.lock().unwrap().push("start".to_string());
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<Summary, GenError> {
+            self.events.lock().unwrap().push("finish".to_string());
+            Ok(self.summary.clone())
+        }
+    }
+
+    /// fails to send the json with the id 2, sending the batches one by one.
+    struct PartialSender {}
+
+    impl Sender for PartialSender {
+        fn send(&mut self, json: &Value, _pretty: bool) -> Result<String, GenError> {
+            if json["id"] == 2 { Err(GenError::new_with_in_sender("the item 2 is failed")) } else { Ok("the item has been sent".to_string()) }
+        }
+
+        fn batch_size(&self) -> usize {
+            3
+        }
+    }
+
+    #[test]
+    fn partial_batch_test() {
+        let batch: Vec<Value> = (1..=3).map(|id| serde_json::json!({"id": id})).collect();
+        let res = PartialSender {}.send_batch(&batch, false);
+        assert_eq!(res.sent, 1);
+        if_let!(res.result => Err(e) => assert!(e.to_string().contains("the item 2 is failed")));
+
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(PartialSender {})];
+        let mut outputs = Outputs::start(&mut outputs, false).unwrap();
+        for v in generate_iter(&mut JsonTemplate::from_str(r#"{"|id": "seq()"}"#, "|").unwrap()).take(7) {
+            outputs.send(&v.unwrap());
+        }
+        // the first batch has the json 1 sent before the failed json 2 and the json 3 left
+        assert_eq!(outputs.finish().unwrap(), vec![Summary::new(5, 2)]);
+    }
+
+    #[test]
+    fn lifecycle_test() {
+        let js_template = JsonTemplate::from_str(r#"{"|id": "seq()"}"#, "|").unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(LifecycleSender { events: events.clone(), summary: Summary::default() })];
+        let mut outputs = Outputs::start(&mut outputs, false).unwrap();
+        for v in generate_iter(&mut js_template.fork()).take(3) {
+            outputs.send(&v.unwrap());
+        }
+        assert_eq!(outputs.finish().unwrap(), vec![Summary::new(2, 1)]);
+        assert_eq!(*events.lock().unwrap(), vec!["start", "1", "2", "3", "finish"]);
+
+        events.lock().unwrap().clear();
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(LifecycleSender { events: events.clone(), summary: Summary::new(7, 0) })];
+        let outputs = Outputs::start(&mut outputs, false).unwrap();
+        assert_eq!(outputs.finish().unwrap(), vec![Summary::new(7, 0)]);
+
+        events.lock().unwrap().clear();
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(LifecycleSender { events: events.clone(), summary: Summary::default() })];
+        assert_eq!(generate_par(&js_template, &Pacing::default().with_count(2), 2, None, false, &mut outputs).unwrap(), 2);
+        assert_eq!(*events.lock().unwrap(), vec!["start", "4", "5", "finish"]);
+        if_let!(generate_par(&JsonTemplate::from_str(r#"{"|id": "int(1,2) -> unique(10)"}"#, "|").unwrap(),
+                             &Pacing::default().with_count(3), 2, None, false, &mut outputs)
+            => Err(e) => assert!(e.to_string().contains("the unique values are exhausted")));
+        assert_eq!(events.lock().unwrap().last().unwrap(), "finish");
+    }
+
+    fn generate_par_values(jt_body: &str, rep: usize, threads: usize, seed: Option<u64>) -> Result<Vec<Value>, GenError> {
+        let js_template = JsonTemplate::from_str(jt_body, "|").unwrap();
+        let values = Arc::new(Mutex::new(vec![]));
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(VecSender { values: values.clone() })];
+        let count = generate_par(&js_template, &Pacing::default().with_count(rep), threads, seed, false, &mut outputs)?;
+        let values = values.lock().unwrap().clone();
+        assert_eq!(count, values.len());
+        Ok(values)
+    }
+
+    #[test]
+    fn generate_par_test() {
+        let jt_body = r#"{
+            "|id": "seq()",
+            "|code": "int(1,100000) -> unique()",
+            "|name": "str(5) -> optional(0.3)",
+            "|items": "seq(100) -> array(0,3)",
+            "|parent": "ref(id)"
+        }"#;
+        fn numbers(values: &[Value], f: fn(&Value) -> Vec<Value>) -> Vec<i64> {
+            values.iter().flat_map(f).map(|v| v.as_i64().unwrap()).collect()
+        }
+        let values = generate_par_values(jt_body, 500, 4, Some(42)).unwrap();
+        assert_eq!(numbers(&values, |v| vec![v["id"].clone()]), (1..=500).collect::<Vec<i64>>());
+        assert!(values.iter().all(|v| v["id"] == v["parent"]));
+        let items = numbers(&values, |v| v["items"].as_array().unwrap().clone());
+        assert_eq!(items, (101..101 + items.len() as i64).collect::<Vec<i64>>());
+        let mut codes = numbers(&values, |v| vec![v["code"].clone()]);
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), 500);
+
+        assert_eq!(values, generate_par_values(jt_body, 500, 1, Some(42)).unwrap());
+        assert_eq!(values, generate_par_values(jt_body, 500, 8, Some(42)).unwrap());
+        assert_ne!(values, generate_par_values(jt_body, 500, 4, Some(43)).unwrap());
+    }
+
+    #[test]
+    fn generate_par_seed_test() {
+        let jt_body = r#"{"|num": "int(1,1000000)", "|name": "str(8)"}"#;
+        let values = generate_par_values(jt_body, 50, 3, Some(42)).unwrap();
+        assert_eq!(values, generate_par_values(jt_body, 50, 1, Some(42)).unwrap());
+
+        // one random generator for all jsons gives other jsons for the same seed
+        let mut js_template = JsonTemplate::from_str(jt_body, "|").unwrap();
+        js_template.set_seed(42);
+        let serial: Vec<Value> = generate_iter(&mut js_template).take(50).map(|v| v.unwrap()).collect();
+        assert_ne!(values, serial);
+
+        if_let!(generate_par_values(jt_body, 5, 0, Some(42))
+            => Err(e) => assert!(e.to_string().contains("the number of threads should be greater than zero")));
+    }
+
+    #[test]
+    fn generate_par_failed_test() {
+        if_let!(generate_par_values(r#"{"|id": "int(1,10) -> unique(100)"}"#, 20, 4, None)
+            => Err(e) => assert!(e.to_string().contains("the unique values are exhausted")));
+        // the fields after the failed one pass their turns, so the other threads are not blocked
+        if_let!(generate_par_values(r#"{"|id": "seq()", "|code": "int(1,10) -> unique(100)", "|next": "seq()"}"#, 20, 4, Some(1))
+            => Err(e) => assert!(e.to_string().contains("the unique values are exhausted")));
+    }
+
     #[test]
     fn full_test() {
         let jt_body = r#"
@@ -1,23 +1,163 @@
-use clap::{App, Arg, ArgMatches};
+use clap::{App, Arg, ArgMatches, ErrorKind, SubCommand};
 use simplelog::*;
-use serde_json::Value;
-use json_gen::sender::{Sender, ConsoleSender};
-use json_gen::sender::file::{FileSender, FolderSender};
+use json_gen::sender::{Sender, ConsoleSender, Summary, BatchResult, string_from};
+use json_gen::sender::file::{FileSender, FolderSender, Framing};
 use json_gen::json_template::JsonTemplate;
-use json_gen::sender::http::CurlSender;
+use json_gen::sender::http::{CurlSender, HttpSender};
+use json_gen::sender::csv::CsvSender;
+use json_gen::sender::table::{Arrays, ObjectLayout};
+use json_gen::sender::sql::{SqlSender, Dialect};
+use json_gen::sender::sqlite::SqliteSender;
+use std::time::Duration;
 use json_gen::generator::generators::read_file_into_string;
-use json_gen::generate;
+use json_gen::{generate_paced, generate_par};
+use json_gen::pacing::{Pacing, Rate, parse_duration};
+use json_gen::validation;
+use json_gen::error::{GenError, GenErrorType, ErrorCode};
+use json_gen::generator::{GeneratorRegistry, Signature};
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::error::Error;
+use std::io::{self, stdout, Write};
+use std::process::exit;
+use std::rc::Rc;
 
 #[macro_use]
 pub extern crate log;
 
+/// the exit code when the json template is invalid or the jsons can not be generated.
+const TEMPLATE_ERROR: i32 = 1;
+/// the exit code when the arguments are invalid.
+const ARGUMENT_ERROR: i32 = 2;
+/// the exit code when a file can not be read or written.
+const IO_ERROR: i32 = 3;
+/// the exit code when the jsons have failed to be sent.
+const SENDER_ERROR: i32 = 4;
+
 fn main() {
-    let args = get_args();
+    let args = match create_args().get_matches_safe() {
+        Ok(args) => args,
+        Err(e) if e.kind == ErrorKind::HelpDisplayed || e.kind == ErrorKind::VersionDisplayed => e.exit(),
+        Err(e) => {
+            eprintln!("{}", e.message);
+            exit(ARGUMENT_ERROR)
+        }
+    };
     if args.is_present("logs") {
-        SimpleLogger::init(LevelFilter::Debug, Config::default()).unwrap()
+        if let Err(e) = SimpleLogger::init(LevelFilter::Debug, Config::default()) {
+            eprintln!("warning: the logs can not be turned on, {}", e)
+        }
+    }
+
+    let res = match args.subcommand() {
+        ("validate", Some(args)) => validate(args),
+        ("generators", Some(args)) => generators(args),
+        _ => run(&args),
+    };
+    match res {
+        Ok(code) => exit(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(exit_code(&e))
+        }
     }
+}
 
-    generate_from_args(&args);
+/// the exit code telling what has failed: the files, the senders, the arguments or the json template.
+fn exit_code(e: &GenError) -> i32 {
+    match (e.code(), e.tpe()) {
+        (ErrorCode::Io, _) => IO_ERROR,
+        (_, GenErrorType::Sender) => SENDER_ERROR,
+        (ErrorCode::Other, GenErrorType::Common) => ARGUMENT_ERROR,
+        _ => TEMPLATE_ERROR,
+    }
+}
+
+/// exits quietly when the reader of the console has gone (e.g. `json-gen -r 1000 | head -1`),
+/// since there is nobody to send the jsons to anymore.
+fn exit_on_broken_pipe(e: &GenError) {
+    let kind = e.source().and_then(|s| s.downcast_ref::<io::Error>()).map(|s| s.kind());
+    if kind == Some(io::ErrorKind::BrokenPipe) {
+        debug!("the console has been closed: {}", e);
+        exit(0)
+    }
+}
+
+/// prints the text to the console.
+fn print(text: &str) -> Result<(), GenError> {
+    writeln!(stdout().lock(), "{}", text).map_err(|e| {
+        let e = GenError::from(e);
+        exit_on_broken_pipe(&e);
+        e
+    })
+}
+
+/// generates the jsons and returns the exit code: 0 if all jsons have been sent and 4 otherwise.
+fn run(args: &ArgMatches) -> Result<i32, GenError> {
+    let mut template = json_template(args)?;
+    let failed = Rc::new(Cell::new(0));
+    let mut outputs: Vec<Box<dyn Sender>> = output(args, &template)?
+        .into_iter()
+        .map(|sender| Box::new(Counted { sender, failed: failed.clone(), summary: Summary::default() }) as Box<dyn Sender>)
+        .collect();
+    generate_from_args(args, &mut template, &mut outputs)?;
+    if failed.get() > 0 {
+        eprintln!("error: {} items have failed to be sent, see the details with --logs", failed.get());
+        return Ok(SENDER_ERROR);
+    }
+    Ok(0)
+}
+
+/// the sender counting the jsons which have failed to be sent by the wrapped sender.
+struct Counted {
+    sender: Box<dyn Sender>,
+    /// the failed jsons of all senders.
+    failed: Rc<Cell<usize>>,
+    /// the totals counted from the results of sending.
+    summary: Summary,
+}
+
+impl Counted {
+    fn count(&mut self, res: BatchResult, items: usize) -> BatchResult {
+        self.summary.sent += res.sent;
+        self.summary.failed += items.saturating_sub(res.sent);
+        res
+    }
+}
+
+impl Sender for Counted {
+    fn send(&mut self, json: &Value, pretty: bool) -> Result<String, GenError> {
+        let res = BatchResult::all(1, self.sender.send(json, pretty));
+        if let Err(e) = &res.result {
+            exit_on_broken_pipe(e)
+        }
+        self.count(res, 1).result
+    }
+
+    fn send_batch(&mut self, jsons: &[Value], pretty: bool) -> BatchResult {
+        let res = self.sender.send_batch(jsons, pretty);
+        if let Err(e) = &res.result {
+            exit_on_broken_pipe(e)
+        }
+        self.count(res, jsons.len())
+    }
+
+    fn batch_size(&self) -> usize {
+        self.sender.batch_size()
+    }
+
+    fn start(&mut self) -> Result<(), GenError> {
+        self.summary = Summary::default();
+        self.sender.start()
+    }
+
+    /// takes the totals of the wrapped sender if it reports them.
+    fn finish(&mut self) -> Result<Summary, GenError> {
+        let summary = self.sender.finish()?;
+        let failed = if summary == Summary::default() { self.summary.failed } else { summary.failed };
+        self.failed.set(self.failed.get() + failed);
+        Ok(summary)
+    }
 }
 
 fn create_args<'a,'b>() -> App<'a,'b> {
@@ -25,40 +165,96 @@ fn create_args<'a,'b>() -> App<'a,'b> {
         .version("0.2.3")
         .author("Boris Zhguchev <zhguchev@gmail.com>")
         .help("The json generator with ability to generate dynamic fields.")
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("checks the json template without generating jsons, \
+                the mistakes are reported with the path of the field and the column in the generator expression")
+                .args(&template_args()))
+        .subcommand(
+            SubCommand::with_name("generators")
+                .about("lists the generators with the parameters, the default values and the examples")
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("to print the generators as the json array")))
+        .args(&template_args())
         .arg(
-            Arg::with_name("jt-file")
-                .short("f")
-                .long("file")
+            Arg::with_name("repeater")
+                .short("r")
+                .long("repeat")
                 .takes_value(true)
-                .allow_hyphen_values(true)
-                .conflicts_with("jt-body")
-                .help("the file containing the json template"))
+                .help("how many repetition needs to perform (1 by default or endless if the duration is set)"))
         .arg(
-            Arg::with_name("jt-body")
-                .short("b")
-                .long("body")
+            Arg::with_name("rate")
+                .long("rate")
                 .takes_value(true)
-                .allow_hyphen_values(true)
-                .conflicts_with("jt-file")
-                .help("the text representation containing the json template"))
+                .help("the number of jsons per the unit (s,m,h) to generate, like 200/s, or the range like 10/s..500/s growing during the ramp-up"))
         .arg(
-            Arg::with_name("repeater")
-                .short("r")
-                .long("repeat")
+            Arg::with_name("duration")
+                .long("duration")
+                .takes_value(true)
+                .help("the time to generate jsons for with the unit (ms,s,m,h,d), like 10m"))
+        .arg(
+            Arg::with_name("ramp-up")
+                .long("ramp-up")
                 .takes_value(true)
-                .help("how many repetition needs to perform"))
+                .requires("rate")
+                .help("the time for the rate given as the range to grow from the first rate to the second one, like 1m"))
         .arg(
-            Arg::with_name("indicator")
-                .short("i")
-                .long("indicator")
+            Arg::with_name("seed")
+                .short("s")
+                .long("seed")
                 .takes_value(true)
-                .help("the prefix signalling the field contains a generator"))
+                .help("the seed for the random generators to get the same jsons every run"))
+        .arg(
+            Arg::with_name("threads")
+                .short("t")
+                .long("threads")
+                .takes_value(true)
+                .help("the number of threads to generate jsons in parallel"))
         .arg(
             Arg::with_name("to-curl")
                 .long("to-curl")
                 .takes_value(true)
                 .allow_hyphen_values(true)
                 .help("to send the request through the curl utility using this param and adding json body (curl utility needs to be installed)"))
+        .arg(
+            Arg::with_name("to-http")
+                .long("to-http")
+                .takes_value(true)
+                .help("to send the generated jsons to the url over http"))
+        .arg(
+            Arg::with_name("http-method")
+                .long("http-method")
+                .takes_value(true)
+                .requires("to-http")
+                .help("the http method to send the jsons (POST by default)"))
+        .arg(
+            Arg::with_name("http-header")
+                .long("http-header")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .requires("to-http")
+                .help("the http header in the form 'Name: value', can be repeated"))
+        .arg(
+            Arg::with_name("http-timeout")
+                .long("http-timeout")
+                .takes_value(true)
+                .requires("to-http")
+                .help("the timeout of the http request in seconds (30 by default)"))
+        .arg(
+            Arg::with_name("batch-size")
+                .long("batch-size")
+                .takes_value(true)
+                .requires("to-http")
+                .help("the number of jsons sent as the json array in one http request (1 by default, sending the jsons as is)"))
+        .arg(
+            Arg::with_name("concurrency")
+                .long("concurrency")
+                .takes_value(true)
+                .requires("to-http")
+                .help("the number of http requests being sent at once (1 by default)"))
         .arg(
             Arg::with_name("to-folder")
                 .long("to-folder")
@@ -71,6 +267,75 @@ fn create_args<'a,'b>() -> App<'a,'b> {
                 .takes_value(true)
                 .allow_hyphen_values(true)
                 .help("save the generated jsons to the file"))
+        .arg(
+            Arg::with_name("to-file-format")
+                .long("to-file-format")
+                .takes_value(true)
+                .possible_values(&["raw", "ndjson", "jsonl", "array"])
+                .requires("to-file")
+                .help("the layout of the jsons in the file: raw (appended as is, by default), ndjson (one json per line) or array (the file is rewritten with one json array)"))
+        .arg(
+            Arg::with_name("to-csv")
+                .long("to-csv")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .help("save the generated jsons to the csv file, one json per row with the columns of the json template"))
+        .arg(
+            Arg::with_name("csv-delimiter")
+                .long("csv-delimiter")
+                .takes_value(true)
+                .requires("to-csv")
+                .help("the delimiter of the values in the csv file, one symbol or tab (, by default)"))
+        .arg(
+            Arg::with_name("csv-arrays")
+                .long("csv-arrays")
+                .takes_value(true)
+                .possible_values(&["json", "indexed"])
+                .requires("to-csv")
+                .help("the way to save the arrays in the csv file: json (one column with json text, by default) or indexed (one column per item)"))
+        .arg(
+            Arg::with_name("to-sql")
+                .long("to-sql")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .requires("sql-table")
+                .help("save the generated jsons to the file as the sql insert statements with the columns of the json template"))
+        .arg(
+            Arg::with_name("sql-table")
+                .long("sql-table")
+                .takes_value(true)
+                .help("the table to insert the jsons into for to-sql and to-sqlite"))
+        .arg(
+            Arg::with_name("sql-dialect")
+                .long("sql-dialect")
+                .takes_value(true)
+                .possible_values(&["postgres", "sqlite", "mysql"])
+                .requires("to-sql")
+                .help("the dialect of the sql statements (postgres by default)"))
+        .arg(
+            Arg::with_name("sql-rows")
+                .long("sql-rows")
+                .takes_value(true)
+                .requires("to-sql")
+                .help("the number of rows inserted by one statement (1 by default)"))
+        .arg(
+            Arg::with_name("sql-objects")
+                .long("sql-objects")
+                .takes_value(true)
+                .help("the way to save the nested objects for to-sql and to-sqlite: json (one column with json text, by default) or flatten (one column per field like geo.city), the particular objects are set with the name like json,geo=flatten"))
+        .arg(
+            Arg::with_name("to-sqlite")
+                .long("to-sqlite")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .requires("sql-table")
+                .help("insert the generated jsons into the table of the sqlite database file, the table is created if it is missing"))
+        .arg(
+            Arg::with_name("sqlite-batch-size")
+                .long("sqlite-batch-size")
+                .takes_value(true)
+                .requires("to-sqlite")
+                .help("the number of jsons inserted in one transaction for to-sqlite (1000 by default)"))
         .arg(
             Arg::with_name("to-console")
                 .long("to-console")
@@ -85,32 +350,139 @@ fn create_args<'a,'b>() -> App<'a,'b> {
                 .help("to print extra logs"))
 }
 
-fn get_args<'a>() -> ArgMatches<'a> {
-    create_args().get_matches()
+/// the arguments giving the json template.
+fn template_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("jt-file")
+            .short("f")
+            .long("file")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .conflicts_with("jt-body")
+            .help("the file containing the json template"),
+        Arg::with_name("jt-body")
+            .short("b")
+            .long("body")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .conflicts_with("jt-file")
+            .help("the text representation containing the json template"),
+        Arg::with_name("indicator")
+            .short("i")
+            .long("indicator")
+            .takes_value(true)
+            .help("the prefix signalling the field contains a generator"),
+    ]
+}
+
+/// parses the value of the argument if it is present.
+fn parsed<T: std::str::FromStr>(args: &ArgMatches, name: &str, expected: &str) -> Result<Option<T>, GenError> {
+    match args.value_of(name) {
+        Some(v) => v.trim().parse().map(Some).map_err(|_| GenError::new_with(
+            format!("the value '{}' of the argument '{}' is invalid, {}", v, name, expected).as_str())),
+        None => Ok(None),
+    }
+}
+
+fn seed(args: &ArgMatches) -> Result<Option<u64>, GenError> {
+    parsed(args, "seed", "the seed should be a positive integer")
+}
+
+fn threads(args: &ArgMatches) -> Result<Option<usize>, GenError> {
+    let expected = "the number of threads should be a positive integer, greater than zero";
+    match parsed(args, "threads", expected)? {
+        Some(0) => Err(GenError::new_with(
+            format!("the value '0' of the argument 'threads' is invalid, {}", expected).as_str())),
+        threads => Ok(threads)
+    }
 }
 
-fn r(args: &ArgMatches) -> usize {
-    args
-        .value_of("repeater")
-        .unwrap_or("1")
-        .parse()
-        .expect("the repetition number should be a positive integer, greater than zero")
+fn r(args: &ArgMatches) -> Result<usize, GenError> {
+    parsed(args, "repeater", "the repetition number should be a positive integer, greater than zero")
+        .map(|r| r.unwrap_or(1))
 }
 
-fn output(args: &ArgMatches) -> Vec<Box<dyn Sender>> {
+fn pacing(args: &ArgMatches) -> Result<Pacing, GenError> {
+    let duration = args.value_of("duration").map(parse_duration).transpose()?;
+    let mut pacing = match duration {
+        Some(d) if !args.is_present("repeater") => Pacing::default().with_duration(d),
+        Some(d) => Pacing::default().with_duration(d).with_count(r(args)?),
+        None => Pacing::default().with_count(r(args)?),
+    };
+    if let Some(rate) = args.value_of("rate") {
+        let ramp_up = args.value_of("ramp-up").map(parse_duration).transpose()?;
+        pacing = pacing.with_rate(Rate::parse(rate, ramp_up)?)
+    }
+    Ok(pacing)
+}
+
+fn output(args: &ArgMatches, template: &JsonTemplate) -> Result<Vec<Box<dyn Sender>>, GenError> {
     let mut outputs: Vec<Box<dyn Sender>> = vec![];
     if let Some(str) = args.value_of("to-file") {
         debug!("new output to the file: {}", str);
-        outputs.push(Box::new(FileSender::new(str.to_string())))
+        let mut sender = FileSender::new(str.to_string())?;
+        if let Some(framing) = args.value_of("to-file-format") {
+            sender = sender.with_framing(Framing::new(framing)?)
+        }
+        outputs.push(Box::new(sender))
+    }
+    if let Some(str) = args.value_of("to-csv") {
+        debug!("new output to the csv file: {}", str);
+        let arrays = Arrays::new(args.value_of("csv-arrays").unwrap_or("json"))?;
+        let delimiter = match args.value_of("csv-delimiter").unwrap_or(",") {
+            "tab" | "\\t" | "\t" => b'\t',
+            d if d.len() == 1 => d.as_bytes()[0],
+            d => return Err(GenError::new_with(
+                format!("the csv delimiter '{}' should be one ascii symbol or tab", d).as_str())),
+        };
+        outputs.push(Box::new(CsvSender::new(str.to_string(), template, arrays).with_delimiter(delimiter)))
+    }
+    let objects = ObjectLayout::new(args.value_of("sql-objects").unwrap_or("json"))?;
+    let table = args.value_of("sql-table").unwrap_or_default();
+    if let Some(str) = args.value_of("to-sql") {
+        debug!("new output to the sql file: {}", str);
+        let dialect = Dialect::new(args.value_of("sql-dialect").unwrap_or("postgres"))?;
+        let rows = parsed(args, "sql-rows", "the number of rows should be a positive integer, greater than zero")?
+            .unwrap_or(1);
+        outputs.push(Box::new(
+            SqlSender::new(str.to_string(), table, template, objects.clone()).with_dialect(dialect).with_rows(rows)))
+    }
+    if let Some(str) = args.value_of("to-sqlite") {
+        debug!("new output to the sqlite database: {}", str);
+        let mut sender = SqliteSender::new(str.to_string(), table, template, objects);
+        if let Some(size) = parsed(args, "sqlite-batch-size", "the batch size should be a positive integer, greater than zero")? {
+            sender = sender.with_batch_size(size)
+        }
+        outputs.push(Box::new(sender))
     }
     if let Some(str) = args.value_of("to-folder") {
         debug!("new output to the folder: {}", str);
-        outputs.push(Box::new(FolderSender::new(str.to_string())))
+        outputs.push(Box::new(FolderSender::new(str.to_string())?))
     }
     if let Some(str) = args.value_of("to-curl") {
         debug!("new output to the server: {}", str);
         outputs.push(Box::new(CurlSender::new(str.to_string())))
     }
+    if let Some(url) = args.value_of("to-http") {
+        debug!("new output to the url: {}", url);
+        let mut sender = HttpSender::new(url);
+        if let Some(method) = args.value_of("http-method") {
+            sender = sender.with_method(method)
+        }
+        for header in args.values_of("http-header").into_iter().flatten() {
+            sender = sender.with_header(header)
+        }
+        if let Some(timeout) = parsed(args, "http-timeout", "the timeout should be a positive number of seconds")? {
+            sender = sender.with_timeout(Duration::from_secs(timeout))
+        }
+        if let Some(size) = parsed(args, "batch-size", "the batch size should be a positive integer, greater than zero")? {
+            sender = sender.with_batch_size(size)
+        }
+        if let Some(concurrency) = parsed(args, "concurrency", "the concurrency should be a positive integer, greater than zero")? {
+            sender = sender.with_concurrency(concurrency)
+        }
+        outputs.push(Box::new(sender))
+    }
     if args.is_present("to-console") {
         debug!("new output to the console");
         outputs.push(Box::new(ConsoleSender {}))
@@ -119,57 +491,187 @@ fn output(args: &ArgMatches) -> Vec<Box<dyn Sender>> {
         debug!("set the output to the console");
         outputs.push(Box::new(ConsoleSender {}))
     }
-    outputs
+    Ok(outputs)
 }
 
-fn json_template(args: &ArgMatches) -> JsonTemplate {
-    debug!("try to parse the json template...");
-    let txt = match (args.value_of("jt-body"), args.value_of("jt-file")) {
+fn template_text(args: &ArgMatches) -> Result<String, GenError> {
+    match (args.value_of("jt-body"), args.value_of("jt-file")) {
         (Some(body), _) => {
             debug!("ready to obtain the json template from the body {}", body);
-            String::from(body)
+            Ok(String::from(body))
         }
         (None, Some(file)) => {
             debug!("ready to obtain the json template from the file {}", file);
-            read_file_into_string(file)
-                .expect("exception with the processing the file!")
+            read_file_into_string(file).map_err(|e| GenError::new_with(
+                format!("the json template can not be read from the file {}, {}", file, e).as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e))
         }
-        (None, None) => panic!("the input file or body containing the json template should be provided!")
-    };
+        (None, None) => Err(GenError::new_with(
+            "the input file or body containing the json template should be provided, see --help"))
+    }
+}
+
+fn json_template(args: &ArgMatches) -> Result<JsonTemplate, GenError> {
+    debug!("try to parse the json template...");
+    let txt = template_text(args)?;
     let indicator = args.value_of("indicator").unwrap_or("|");
     debug!("the json template with indicator[{}] {}", indicator, txt);
-    match JsonTemplate::from_str(txt.as_str(), indicator) {
-        Ok(t) => t,
-        Err(e) => panic!("error while parsing json : {:?}", e),
+    JsonTemplate::from_str(txt.as_str(), indicator)
+}
+
+/// checks the json template and prints the mistakes, returns the exit code: 0 if the template is valid and 1 otherwise.
+fn validate(args: &ArgMatches) -> Result<i32, GenError> {
+    let issues = validation::validate(template_text(args)?.as_str(), args.value_of("indicator").unwrap_or("|"));
+    if issues.is_empty() {
+        print("the json template is valid")?;
+        return Ok(0);
+    }
+    for issue in issues.iter() {
+        eprintln!("error: {}", issue)
+    }
+    eprintln!("the json template has {} error(s)", issues.len());
+    Ok(TEMPLATE_ERROR)
+}
+
+/// prints the generators either as the text or as the json array.
+fn generators(args: &ArgMatches) -> Result<i32, GenError> {
+    let registry = GeneratorRegistry::default();
+    if args.is_present("json") {
+        let signatures = registry.signatures().into_iter().map(signature_json).collect();
+        print(string_from(&Value::Array(signatures), true)?.as_str())?;
+    } else {
+        let signatures: Vec<String> = registry.signatures().into_iter().map(signature_text).collect();
+        print(signatures.join("\n\n").as_str())?;
+    }
+    Ok(0)
+}
+
+fn signature_text(signature: &Signature) -> String {
+    let mut lines = vec![signature.to_string(), format!("    {}", signature.description)];
+    for param in signature.params.iter() {
+        let default = match param.default {
+            Some("") => ", '' by default".to_string(),
+            Some(default) => format!(", {} by default", default),
+            None if param.required => ", required".to_string(),
+            None => ", optional".to_string(),
+        };
+        lines.push(format!("    - {}: {}{}", param.name, param.description, default))
     }
+    lines.push(format!("    example: {}", signature.example));
+    lines.join("\n")
 }
 
-fn generate_from_args(args: &ArgMatches) -> Vec<Value> {
-    generate(&mut json_template(&args), r(&args), args.is_present("pretty-js"), &mut output(&args))
+fn signature_json(signature: &Signature) -> Value {
+    json!({
+        "name": signature.name,
+        "description": signature.description,
+        "params": signature.params.iter().map(|p| json!({
+            "name": p.name,
+            "default": p.default,
+            "required": p.required,
+            "description": p.description,
+        })).collect::<Vec<Value>>(),
+        "example": signature.example,
+    })
+}
+
+fn generate_from_args(args: &ArgMatches, template: &mut JsonTemplate, outputs: &mut Vec<Box<dyn Sender>>) -> Result<usize, GenError> {
+    let pacing = pacing(args)?;
+    let seed = seed(args)?;
+    match threads(args)? {
+        Some(threads) if threads > 1 => generate_par(template, &pacing, threads, seed, args.is_present("pretty-js"), outputs),
+        _ => {
+            if let Some(seed) = seed {
+                template.set_seed(seed)
+            }
+            generate_paced(template, &pacing, args.is_present("pretty-js"), outputs)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{create_args, generate_from_args};
+    use crate::{create_args, generate_from_args, json_template};
+    use json_gen::generate_iter;
+    use json_gen::json_template::JsonTemplate;
+    use json_gen::sender::Sender;
+    use serde_json::Value;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    /// keeps the sent jsons to check them.
+    struct VecSender {
+        values: Rc<RefCell<Vec<Value>>>
+    }
+
+    impl Sender for VecSender {
+        fn send(&mut self, json: &Value, _pretty: bool) -> Result<String, json_gen::error::GenError> {
+            self.values.borrow_mut().push(json.clone());
+            Ok("the item has been kept".to_string())
+        }
+    }
+
+    fn generate_values(args: &[&str]) -> Vec<Value> {
+        let values = Rc::new(RefCell::new(vec![]));
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(VecSender { values: values.clone() })];
+        let args = create_args().get_matches_from(args);
+        let count = generate_from_args(&args, &mut json_template(&args).unwrap(), &mut outputs).unwrap();
+        let values = values.borrow().clone();
+        assert_eq!(count, values.len());
+        values
+    }
 
     #[test]
     fn find_json_text() {
         let jt_body = r#"{"|id": "uuid()"}"#;
-        let args =
-            create_args()
-                .get_matches_from(
-                    vec![
-                        "",
-                        format!("--body={}", jt_body).as_str(),
-                        "--pretty",
-                    ]);
-        let res = generate_from_args(&args);
+        let res = generate_values(&["", format!("--body={}", jt_body).as_str(), "--pretty"]);
         assert_eq!(res.len(), 1);
-        assert_eq!(res.get(0)
+        assert_eq!(res.first()
                        .and_then(|v| v.as_object())
                        .unwrap()
                        .get("id")
                        .and_then(|e| e.as_str())
                        .unwrap().len(), 36);
     }
+
+    #[test]
+    fn seed_test() {
+        let jt_body = r#"{"|id": "uuid()","|num": "int(1,1000)"}"#;
+        let body = format!("--body={}", jt_body);
+        let args = ["", body.as_str(), "--repeat=5", "--seed=42"];
+        let values = generate_values(&args);
+        assert_eq!(values.len(), 5);
+        assert_eq!(values, generate_values(&args));
+    }
+
+    #[test]
+    fn threads_test() {
+        let jt_body = r#"{"|id": "seq()","|num": "int(1,1000)"}"#;
+        let body = format!("--body={}", jt_body);
+        let values = generate_values(&["", body.as_str(), "--repeat=100", "--seed=42", "--threads=4"]);
+        assert_eq!(values.len(), 100);
+        let ids: Vec<i64> = values.iter().map(|v| v["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, (1..=100).collect::<Vec<i64>>());
+        assert_eq!(values, generate_values(&["", body.as_str(), "-r", "100", "-s", "42", "-t", "2"]));
+
+        // one thread generates the same jsons as the template seeded in the library
+        let mut template = JsonTemplate::from_str(jt_body, "|").unwrap();
+        template.set_seed(42);
+        let expected: Vec<Value> = generate_iter(&mut template).take(100).map(|v| v.unwrap()).collect();
+        assert_eq!(generate_values(&["", body.as_str(), "-r", "100", "-s", "42"]), expected);
+        assert_eq!(generate_values(&["", body.as_str(), "-r", "100", "-s", "42", "-t", "1"]), expected);
+    }
+
+    #[test]
+    fn rate_test() {
+        let body = format!("--body={}", r#"{"|id": "seq()"}"#);
+        let start = std::time::Instant::now();
+        let values = generate_values(&["", body.as_str(), "--duration=300ms", "--rate=20/s"]);
+        assert_eq!(values.len(), 6);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(250));
+
+        let values = generate_values(&["", body.as_str(), "-r", "3", "--duration=1m", "--rate=0/s..6000/m", "--ramp-up=1s"]);
+        assert_eq!(values.len(), 3);
+    }
 }
@@ -0,0 +1,222 @@
+//! ### Pacing
+//! The module which is responsible to throttle the generation and to bound it by the number of jsons or by the time.
+//! The pacing is applied to the loop of the generation therefore every sender gets the jsons paced.
+//! The jsons are scheduled from the moment the generation starts,
+//! so the slow jsons or senders do not shift the next ones and the rate keeps stable.
+
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::error::GenError;
+
+/// The number of jsons per second the generation emits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rate {
+    /// the same rate all the time.
+    Fixed(f64),
+    /// the rate growing (or falling) linearly from the first rate to the second one during the ramp-up
+    /// and staying the same after it.
+    Linear(f64, f64, Duration),
+}
+
+impl Rate {
+    /// parses the rate like `200/s`, `30/m`, `1000/h` or `200` (per second)
+    /// or the linear rate like `10/s..500/s` growing during the ramp-up.
+    pub fn parse(rate: &str, ramp_up: Option<Duration>) -> Result<Rate, GenError> {
+        match (rate.split_once(".."), ramp_up) {
+            (Some((from, to)), Some(ramp_up)) if !ramp_up.is_zero() => {
+                let (from, to) = (parse_per_second(from, true)?, parse_per_second(to, false)?);
+                Ok(Rate::Linear(from, to, ramp_up))
+            }
+            (Some(_), _) => Err(GenError::new_with(
+                format!("the rate '{}' changing linearly needs the ramp-up duration greater than zero", rate).as_str())),
+            (None, _) => Ok(Rate::Fixed(parse_per_second(rate, false)?)),
+        }
+    }
+
+    /// the time from the start when the json with the index (starting from 0) should be emitted.
+    fn due(&self, idx: usize) -> Duration {
+        let n = idx as f64;
+        let secs = match *self {
+            Rate::Fixed(r) => n / r,
+            Rate::Linear(from, to, ramp_up) => {
+                let t = ramp_up.as_secs_f64();
+                let ramped = (from + to) * t / 2.0;
+                if n <= ramped {
+                    // the root of the equation from * x + (to - from) / (2 * t) * x^2 = n
+                    let k = (to - from) / (2.0 * t);
+                    let d = from * from + 4.0 * k * n;
+                    if n == 0.0 { 0.0 } else { 2.0 * n / (from + d.max(0.0).sqrt()) }
+                } else {
+                    t + (n - ramped) / to
+                }
+            }
+        };
+        Duration::from_secs_f64(secs)
+    }
+}
+
+fn parse_per_second(rate: &str, zero: bool) -> Result<f64, GenError> {
+    let err = || GenError::new_with(
+        format!("the rate '{}' should be a positive number per the unit (s,m,h) like 200/s", rate).as_str());
+    let (num, unit) = rate.trim().split_once('/').unwrap_or((rate.trim(), "s"));
+    let num: f64 = num.trim().parse().map_err(|_| err())?;
+    let per_second = match unit.trim() {
+        "s" => num,
+        "m" => num / 60.0,
+        "h" => num / 3600.0,
+        _ => return Err(err())
+    };
+    if per_second.is_finite() && (per_second > 0.0 || zero && per_second == 0.0) {
+        Ok(per_second)
+    } else {
+        Err(err())
+    }
+}
+
+/// parses the duration like `500ms`, `30s`, `10m`, `2h` or `1d`.
+pub fn parse_duration(duration: &str) -> Result<Duration, GenError> {
+    let err = || GenError::new_with(
+        format!("the duration '{}' should be a positive number with the unit (ms,s,m,h,d) like 10m", duration).as_str());
+    let idx = duration.find(|c: char| c.is_ascii_alphabetic()).ok_or_else(err)?;
+    let (num, unit) = duration.split_at(idx);
+    let num: u64 = num.trim().parse().map_err(|_| err())?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(num)),
+        "s" => Ok(Duration::from_secs(num)),
+        "m" => Ok(Duration::from_secs(num * 60)),
+        "h" => Ok(Duration::from_secs(num * 3600)),
+        "d" => Ok(Duration::from_secs(num * 24 * 3600)),
+        _ => Err(err())
+    }
+}
+
+/// The bounds and the rate of the generation.
+/// By default, the generation is endless and runs flat out.
+/// # Examples
+/// ```rust
+/// use json_gen::pacing::{Pacing, Rate};
+/// use std::time::Duration;
+///
+///     let pacing = Pacing::default()
+///         .with_duration(Duration::from_secs(600))
+///         .with_rate(Rate::parse("10/s..500/s", Some(Duration::from_secs(60))).unwrap());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Pacing {
+    count: Option<usize>,
+    duration: Option<Duration>,
+    rate: Option<Rate>,
+}
+
+impl Pacing {
+    /// generates the given number of jsons at most.
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+    /// generates the jsons until the time runs out.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+    /// emits the jsons with the given rate.
+    pub fn with_rate(mut self, rate: Rate) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// starts the clock of the generation.
+    pub(crate) fn start(&self) -> Pacer<'_> {
+        Pacer { pacing: self, start: Instant::now() }
+    }
+}
+
+/// The clock of the generation shared between the threads.
+pub(crate) struct Pacer<'a> {
+    pacing: &'a Pacing,
+    start: Instant,
+}
+
+impl<'a> Pacer<'a> {
+    /// waits until the json with the index (starting from 0) is due.
+    /// Returns false if the json is out of the bounds and the generation should stop.
+    pub(crate) fn wait(&self, idx: usize) -> bool {
+        if self.pacing.count.map(|c| idx >= c).unwrap_or(false) {
+            return false;
+        }
+        let due = self.pacing.rate.as_ref().map(|r| r.due(idx)).unwrap_or_default();
+        if let Some(duration) = self.pacing.duration {
+            if due >= duration || self.start.elapsed() >= duration {
+                return false;
+            }
+        }
+        let elapsed = self.start.elapsed();
+        if due > elapsed {
+            thread::sleep(due - elapsed)
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pacing::{Rate, Pacing, parse_duration};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(Rate::parse("200/s", None).unwrap(), Rate::Fixed(200.0));
+        assert_eq!(Rate::parse("120/m", None).unwrap(), Rate::Fixed(2.0));
+        assert_eq!(Rate::parse("50", None).unwrap(), Rate::Fixed(50.0));
+        assert_eq!(Rate::parse("0/s..60/m", Some(Duration::from_secs(10))).unwrap(),
+                   Rate::Linear(0.0, 1.0, Duration::from_secs(10)));
+
+        if_let!(Rate::parse("0/s", None) => Err(e) => assert!(e.to_string().contains("the rate '0/s'")));
+        if_let!(Rate::parse("10/d", None) => Err(e) => assert!(e.to_string().contains("the rate '10/d'")));
+        if_let!(Rate::parse("10/s..500/s", None) => Err(e) => assert!(e.to_string().contains("ramp-up")));
+
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        if_let!(parse_duration("10") => Err(e) => assert!(e.to_string().contains("the duration '10'")));
+        if_let!(parse_duration("-1s") => Err(e) => assert!(e.to_string().contains("the duration '-1s'")));
+    }
+
+    #[test]
+    fn due_test() {
+        let rate = Rate::Fixed(200.0);
+        assert_eq!(rate.due(0), Duration::from_secs(0));
+        assert_eq!(rate.due(400), Duration::from_secs(2));
+
+        // 10/s..30/s during 10s gives 200 jsons for the ramp-up and 30 jsons every second after it.
+        let rate = Rate::Linear(10.0, 30.0, Duration::from_secs(10));
+        assert_eq!(rate.due(0), Duration::from_secs(0));
+        assert!((rate.due(10).as_secs_f64() - 0.916).abs() < 0.001);
+        assert!((rate.due(200).as_secs_f64() - 10.0).abs() < 0.001);
+        assert!((rate.due(230).as_secs_f64() - 11.0).abs() < 0.001);
+
+        let rate = Rate::Linear(0.0, 2.0, Duration::from_secs(10));
+        assert!((rate.due(1).as_secs_f64() - 10.0_f64.sqrt()).abs() < 0.001);
+
+        let rate = Rate::Linear(30.0, 10.0, Duration::from_secs(10));
+        assert!((rate.due(200).as_secs_f64() - 10.0).abs() < 0.001);
+        assert!(rate.due(100) < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn wait_test() {
+        let pacing = Pacing::default().with_count(3);
+        let pacer = pacing.start();
+        assert!(pacer.wait(0) && pacer.wait(2) && !pacer.wait(3));
+
+        let pacing = Pacing::default().with_rate(Rate::Fixed(100.0)).with_duration(Duration::from_millis(100));
+        let start = Instant::now();
+        let pacer = pacing.start();
+        let count = (0..).take_while(|idx| pacer.wait(*idx)).count();
+        assert_eq!(count, 10);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+
+        let pacing = Pacing::default().with_duration(Duration::from_millis(50));
+        let pacer = pacing.start();
+        assert!((0..).take_while(|idx| pacer.wait(*idx)).count() > 10);
+    }
+}
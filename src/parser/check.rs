@@ -0,0 +1,189 @@
+//! Locating the mistakes in the generator expressions.
+//! Every generator of the chain `a() -> b()` is parsed on its own, so the mistake gets the name of the generator,
+//! the span in the expression and the reason given by the generator function.
+
+use std::ops::Range;
+use nom::combinator::all_consuming;
+use nom::error::ErrorKind;
+use crate::error::{GenError, ErrorCode};
+use crate::parser::generators::GeneratorRegistry;
+
+/// finds the mistakes in the expression: the unknown generators first, then the first generator
+/// which can not be parsed and at last the generator which can not follow the previous ones in the chain.
+/// The spans of the mistakes are in bytes from the start of the expression.
+pub fn check(expression: &str, registry: &GeneratorRegistry) -> Vec<GenError> {
+    let error = |reason: &str, code: ErrorCode, span: Range<usize>|
+        GenError::new_with_in_parser(reason).with_code(code).with_span(expression, span);
+
+    let masked = mask(expression);
+    let unknown: Vec<GenError> = functions(expression, &masked).into_iter()
+        .filter(|(_, name)| !registry.contains(name))
+        .map(|(start, name)| error(format!("the generator '{}' is unknown", name).as_str(),
+                                   ErrorCode::UnknownGenerator, start..start + name.len()).with_generator(name))
+        .collect();
+    if !unknown.is_empty() {
+        return unknown;
+    }
+
+    let mut gens = vec![];
+    for (offset, segment) in split_chain(expression, &masked) {
+        let start = offset + segment.len() - segment.trim_start().len();
+        let end = offset + segment.trim_end().len();
+        let name = identifier(segment.trim_start());
+        let segment = segment.trim_end();
+        let p = match registry.parser(name) {
+            Some(p) => p,
+            None if name.is_empty() =>
+                return vec![error("the generator function is expected", ErrorCode::Syntax, start..end.max(start))],
+            None => return vec![error(format!("the generator '{}' is unknown", name).as_str(),
+                                      ErrorCode::UnknownGenerator, start..start + name.len()).with_generator(name)],
+        };
+        match all_consuming(move |i| p(i, registry))(segment) {
+            Ok((_, g)) => gens.push((start..end, name, g)),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                let rest = e.input.trim_start();
+                let pos = offset + segment.len() - rest.len();
+                let args_end = if segment.ends_with(')') { end - 1 } else { end }.max(pos);
+                let e = match e.cause {
+                    Some(cause) => error(format!("the arguments of the generator '{}' are invalid, {}", name, cause.reason()).as_str(),
+                                         ErrorCode::InvalidArguments, pos..args_end).with_source(*cause),
+                    None if rest.is_empty() && mask(segment).contains('(') =>
+                        error(format!("the brackets of the generator '{}' are not closed", name).as_str(), ErrorCode::Syntax, pos..end),
+                    None if rest.is_empty() =>
+                        error(format!("the generator '{}' should have the arguments in the brackets like {}()", name, name).as_str(),
+                              ErrorCode::Syntax, pos..end),
+                    None if e.kind == ErrorKind::MapRes =>
+                        error(format!("the arguments of the generator '{}' are invalid", name).as_str(),
+                              ErrorCode::InvalidArguments, pos..args_end),
+                    None => error(format!("the input '{}' is unexpected", rest).as_str(), ErrorCode::Syntax, pos..end),
+                };
+                return vec![e.with_generator(name)];
+            }
+            Err(nom::Err::Incomplete(_)) =>
+                return vec![error("the expression is incomplete", ErrorCode::Syntax, start..end).with_generator(name)],
+        }
+    }
+
+    let mut gens = gens.into_iter();
+    if let Some((_, _, mut res)) = gens.next() {
+        for (span, name, g) in gens {
+            match g.merge(&res) {
+                Ok(merged) => res = merged,
+                Err(e) => return vec![error(
+                    format!("the generator '{}' can not follow the previous generators, {}", name, e.reason()).as_str(),
+                    ErrorCode::InvalidChain, span).with_generator(name)]
+            }
+        }
+    }
+    vec![]
+}
+
+/// replaces the quoted strings with the spaces of the same length,
+/// so the brackets, the arrows and the names inside the strings are not taken into account.
+fn mask(expression: &str) -> String {
+    let mut res = String::with_capacity(expression.len());
+    let (mut quoted, mut escaped) = (false, false);
+    for c in expression.chars() {
+        let hidden = quoted;
+        if !quoted {
+            quoted = c == '\''
+        } else if escaped {
+            escaped = false
+        } else if c == '\\' {
+            escaped = true
+        } else if c == '\'' {
+            quoted = false
+        }
+        if hidden || quoted {
+            res.push_str(" ".repeat(c.len_utf8()).as_str())
+        } else {
+            res.push(c)
+        }
+    }
+    res
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn identifier(text: &str) -> &str {
+    let end = text.find(|c| !is_name_char(c)).unwrap_or(text.len());
+    &text[..end]
+}
+
+/// the names followed by the brackets along with their offsets.
+fn functions<'a>(expression: &'a str, masked: &str) -> Vec<(usize, &'a str)> {
+    let mut res = vec![];
+    let mut idx = 0;
+    while let Some(start) = masked[idx..].find(is_name_char).map(|s| s + idx) {
+        let name = identifier(&masked[start..]);
+        let end = start + name.len();
+        if !name.starts_with(|c: char| c.is_ascii_digit()) && masked[end..].trim_start().starts_with('(') {
+            res.push((start, &expression[start..end]))
+        }
+        idx = end;
+    }
+    res
+}
+
+/// splits the chain by the arrows which are not inside the brackets.
+fn split_chain<'a>(expression: &'a str, masked: &str) -> Vec<(usize, &'a str)> {
+    let mut res = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (idx, c) in masked.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '-' if depth == 0 && masked[idx..].starts_with("->") => {
+                res.push((start, &expression[start..idx]));
+                start = idx + 2;
+            }
+            _ => ()
+        }
+    }
+    res.push((start, &expression[start..]));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::check::check;
+    use crate::parser::generators::BUILT_IN;
+    use crate::error::ErrorCode;
+    use std::error::Error;
+
+    #[test]
+    fn check_test() {
+        assert!(check("fmt('{}-{}', str_from_list('a->b(c)', d), uuid()) -> nullable()", &BUILT_IN).is_empty());
+
+        let errors = check("int(1,2) -> arrayy(3) -> nulable()", &BUILT_IN);
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].code(), errors[0].generator(), errors[0].span()),
+                   (ErrorCode::UnknownGenerator, Some("arrayy"), Some(12..18)));
+        assert_eq!(errors[1].generator(), Some("nulable"));
+
+        let error = |expression: &str| {
+            let errors = check(expression, &BUILT_IN);
+            assert_eq!(errors.len(), 1);
+            errors.into_iter().next().unwrap()
+        };
+        let e = error("str(3) -> array(-1)");
+        assert_eq!((e.code(), e.generator(), e.span()), (ErrorCode::InvalidArguments, Some("array"), Some(16..18)));
+        assert_eq!(e.reason(), "the arguments of the generator 'array' are invalid, \
+        the array length should be a positive number or a range min,max");
+        assert!(e.source().is_some());
+
+        let e = error("str_from_file(jsons/missing,;)");
+        assert_eq!(e.code(), ErrorCode::InvalidArguments);
+        assert_eq!(e.source().and_then(|e| e.source()).map(|e| e.is::<std::io::Error>()), Some(true));
+
+        let e = error("uuid() x");
+        assert_eq!((e.code(), e.span()), (ErrorCode::Syntax, Some(7..8)));
+        assert!(error("int(1,2").reason().contains("are not closed"));
+        assert!(error("uuid").reason().contains("like uuid()"));
+        assert!(error("uuid() -> ").reason().contains("is expected"));
+        let e = error("int() -> uuid()");
+        assert_eq!((e.code(), e.span()), (ErrorCode::InvalidChain, Some(9..15)));
+    }
+}
@@ -1,147 +1,572 @@
 use std::str;
+use std::sync::Arc;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::borrow::Cow;
 use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    combinator::map_res,
+    bytes::complete::{tag, take_while1},
+    combinator::{all_consuming, map_res},
+    error::{ErrorKind, ParseError},
     multi::separated_list0,
     sequence::{preceded, terminated},
-    IResult,
 };
+use once_cell::sync::Lazy;
 use crate::generator::{GeneratorFunc, Generator};
-use crate::generator::generators::{Sequence, UUID, CurrentDateTime, RandomString, RandomInt, RandomFromFile, RandomFromList, RandomArray, RandomBool};
-use crate::parser::{func, args_string, args, str_to_int, sp, GenError};
+use crate::generator::generators::{Sequence, UUID, RandomString, RandomInt, RandomFromFile, RandomFromList, RandomArray, RandomBool, RandomFloat, Reference, Format, Nullable, Optional, Unique};
+use crate::generator::regex::RandomRegex;
+use crate::generator::datetime::{RandomDateTime, DtFormat};
+use crate::parser::{func, args_string, args, args_with_generators, str_to_int, str_to_float, sp, GenError, Arg, weighted, weighted_string, pattern_string, Res, ExprError};
+use crate::parser::check::check;
+use crate::generator::from_string::FromStringTo;
+use serde_json::Value;
 
-fn current_dt(i: &str) -> IResult<&str, Generator> {
-    func("dt",
+/// The description of the generator function shown to the users: the parameters, their defaults and the example.
+/// The built-in generators define it next to their parsers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Signature {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [Param],
+    /// the expression calling the generator, the modifiers are shown in the chain.
+    pub example: &'static str,
+}
+
+/// The parameter of the generator function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Param {
+    pub name: &'static str,
+    /// the value taken if the argument is omitted.
+    pub default: Option<&'static str>,
+    /// the argument should be given.
+    pub required: bool,
+    pub description: &'static str,
+}
+
+impl Param {
+    /// the parameter which should be given.
+    pub const fn required(name: &'static str, description: &'static str) -> Self {
+        Param { name, default: None, required: true, description }
+    }
+    /// the parameter which can be omitted and has no default value.
+    pub const fn optional(name: &'static str, description: &'static str) -> Self {
+        Param { name, default: None, required: false, description }
+    }
+    /// the parameter taking the default value if it is omitted.
+    pub const fn with_default(name: &'static str, default: &'static str, description: &'static str) -> Self {
+        Param { name, default: Some(default), required: false, description }
+    }
+}
+
+impl Signature {
+    /// the argument with the index or the default value of the parameter with the same index
+    /// if the argument is omitted or empty.
+    fn arg<'a>(&self, elems: &[&'a str], idx: usize) -> &'a str {
+        elems.get(idx).copied()
+            .filter(|e| !e.is_empty())
+            .or_else(|| self.params.get(idx).and_then(|p| p.default))
+            .unwrap_or("")
+    }
+    /// the argument with the index parsed into the value,
+    /// the default value of the parameter is taken if the argument is omitted, empty or invalid.
+    fn parsed_arg<T: FromStr + Default>(&self, elems: &[&str], idx: usize) -> T {
+        elems.get(idx)
+            .and_then(|e| e.trim().parse().ok())
+            .or_else(|| self.default_value(idx))
+            .unwrap_or_default()
+    }
+    /// the default value of the parameter with the index parsed into the value.
+    fn default_value<T: FromStr>(&self, idx: usize) -> Option<T> {
+        self.params.get(idx).and_then(|p| p.default).and_then(|d| d.parse().ok())
+    }
+}
+
+/// the call like `seq(start=0, step=1)`, the optional parameters without defaults are in the brackets.
+impl Display for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<String> = self.params.iter()
+            .map(|p| match p.default {
+                Some("") => format!("{}=''", p.name),
+                Some(default) => format!("{}={}", p.name, default),
+                None if p.required => p.name.to_string(),
+                None => format!("[{}]", p.name),
+            })
+            .collect();
+        write!(f, "{}({})", self.name, params.join(", "))
+    }
+}
+
+/// extracts the optional format and time zone of the date time generators, the format goes with the index.
+fn dt_format_tz<'a>(signature: &Signature, elems: &[&'a str], idx: usize) -> (DtFormat, &'a str) {
+    let elems: Vec<&str> = elems.iter().map(|e| e.trim()).collect();
+    (DtFormat::new(signature.arg(&elems, idx)), signature.arg(&elems, idx + 1))
+}
+
+const DT: Signature = Signature {
+    name: "dt",
+    description: "the current date and time",
+    params: &[
+        Param::with_default("format", "%Y-%m-%d %H:%M:%S", "the format of chrono, epoch gives the seconds and epoch_ms the milliseconds since the epoch as numbers"),
+        Param::with_default("time_zone", "UTC", "the name of the time zone like Europe/Berlin"),
+    ],
+    example: "dt(%Y-%m-%d)",
+};
+
+fn current_dt(i: &str) -> Res<'_, Generator> {
+    func(DT.name,
          args_string(|elems| {
-             let format =
-                 elems.get(0)
-                     .filter(|e| !e.is_empty())
-                     .map(move |f| f.trim())
-                     .unwrap_or("%Y-%m-%d %H:%M:%S").to_string();
-             new(CurrentDateTime { format })
+             let (format, tz) = dt_format_tz(&DT, &elems, 0);
+             new(RandomDateTime::now(format, tz)?)
          }))(i)
 }
 
-fn uuid(i: &str) -> IResult<&str, Generator> {
-    func("uuid", args_string(|_| { new(UUID {}) }))(i)
+const DT_BETWEEN: Signature = Signature {
+    name: "dt_between",
+    description: "the random date and time between the start and the end inclusively",
+    params: &[
+        Param::required("start", "the date, the date time or the date time with the offset like 2020-01-02T10:00:00+02:00"),
+        Param::required("end", "the date (covering the whole day), the date time or the date time with the offset"),
+        Param::with_default("format", "%Y-%m-%d %H:%M:%S", "the format of chrono, epoch gives the seconds and epoch_ms the milliseconds since the epoch as numbers"),
+        Param::with_default("time_zone", "UTC", "the name of the time zone like Europe/Berlin"),
+    ],
+    example: "dt_between(2020-01-01, 2024-12-31, %Y-%m-%d)",
+};
+
+fn dt_between(i: &str) -> Res<'_, Generator> {
+    func(DT_BETWEEN.name,
+         args_string(|elems| {
+             match elems.as_slice() {
+                 [start, end, ..] if elems.len() <= 4 => {
+                     let (format, tz) = dt_format_tz(&DT_BETWEEN, &elems, 2);
+                     new(RandomDateTime::between(start.trim(), end.trim(), format, tz)?)
+                 }
+                 _ => Err(GenError::new_with_in_parser("the dt_between should have the start, the end and optionally the format and the time zone"))
+             }
+         }))(i)
 }
 
-fn bool(i: &str) -> IResult<&str, Generator> {
-    func("bool", args_string(|_| { new(RandomBool::new()) }))(i)
+const DT_OFFSET: Signature = Signature {
+    name: "dt_offset",
+    description: "the random date and time between the moments shifted from now",
+    params: &[
+        Param::required("from", "the offset from now with the unit (ms,s,m,h,d,w) like -30d or 0"),
+        Param::required("to", "the offset from now with the unit (ms,s,m,h,d,w) like +2h or 0"),
+        Param::with_default("format", "%Y-%m-%d %H:%M:%S", "the format of chrono, epoch gives the seconds and epoch_ms the milliseconds since the epoch as numbers"),
+        Param::with_default("time_zone", "UTC", "the name of the time zone like Europe/Berlin"),
+    ],
+    example: "dt_offset(-30d, +2h)",
+};
+
+fn dt_offset(i: &str) -> Res<'_, Generator> {
+    func(DT_OFFSET.name,
+         args_string(|elems| {
+             match elems.as_slice() {
+                 [from, to, ..] if elems.len() <= 4 => {
+                     let (format, tz) = dt_format_tz(&DT_OFFSET, &elems, 2);
+                     new(RandomDateTime::offset(from.trim(), to.trim(), format, tz)?)
+                 }
+                 _ => Err(GenError::new_with_in_parser("the dt_offset should have two offsets and optionally the format and the time zone"))
+             }
+         }))(i)
 }
 
+const UUID: Signature = Signature {
+    name: "uuid",
+    description: "the random uuid",
+    params: &[],
+    example: "uuid()",
+};
+
+fn uuid(i: &str) -> Res<'_, Generator> {
+    func(UUID.name, args_string(|_| { new(UUID::new()) }))(i)
+}
 
-fn sequence(i: &str) -> IResult<&str, Generator> {
-    func("seq", args_string(|elems| {
-        new({
-            let val =
-                if let Some(Ok(new_val)) = elems.get(0).map(|e| e.parse()) { new_val } else { 0 };
+const BOOL: Signature = Signature {
+    name: "bool",
+    description: "the random boolean",
+    params: &[],
+    example: "bool()",
+};
 
-            let step =
-                if let Some(Ok(new_step)) = elems.get(1).map(|e| e.parse()) { new_step } else { 1 };
+fn bool(i: &str) -> Res<'_, Generator> {
+    func(BOOL.name, args_string(|_| { new(RandomBool::new()) }))(i)
+}
 
-            Sequence { val, step }
-        })
+const SEQ: Signature = Signature {
+    name: "seq",
+    description: "the sequentially increasing numbers",
+    params: &[
+        Param::with_default("start", "0", "the number to start from"),
+        Param::with_default("step", "1", "the increment of the number"),
+    ],
+    example: "seq(10,2)",
+};
+
+fn sequence(i: &str) -> Res<'_, Generator> {
+    func(SEQ.name, args_string(|elems| {
+        new(Sequence::new(SEQ.parsed_arg(&elems, 0), SEQ.parsed_arg(&elems, 1)))
     }),
     )(i)
 }
 
-fn random_string(i: &str) -> IResult<&str, Generator> {
-    func("str", args_string(|elems| {
-        new({
-            let n =
-                if let Some(Ok(new_n)) = elems.get(0).map(|e| e.parse()) { new_n } else { 0 };
-
-            let prefix =
-                if let Some(Ok(new_p)) = elems.get(1).map(|e| e.parse()) { new_p } else { String::new() };
+const STR: Signature = Signature {
+    name: "str",
+    description: "the random string of letters and digits with the prefix and the suffix",
+    params: &[
+        Param::with_default("size", "0", "the number of the random letters and digits"),
+        Param::with_default("prefix", "", "the string put before the random part"),
+        Param::with_default("suffix", "", "the string put after the random part"),
+    ],
+    example: "str(10,abc,cde)",
+};
 
-            let suffix =
-                if let Some(Ok(new_s)) = elems.get(2).map(|e| e.parse()) { new_s } else { String::new() };
+fn random_string(i: &str) -> Res<'_, Generator> {
+    func(STR.name, args_string(|elems| {
+        new(RandomString::new_with(STR.parsed_arg(&elems, 0),
+                                   STR.arg(&elems, 1).to_string(),
+                                   STR.arg(&elems, 2).to_string()))
+    }))(i)
+}
 
+const INT: Signature = Signature {
+    name: "int",
+    description: "the random integer between the bounds",
+    params: &[
+        Param::with_default("min", "0", "the lower bound"),
+        Param::with_default("max", "1000", "the upper bound"),
+    ],
+    example: "int(1,100)",
+};
 
-            RandomString::new_with(n, prefix, suffix)
-        })
+fn random_int(i: &str) -> Res<'_, Generator> {
+    func(INT.name, args_string(|elems| {
+        let (min, max): (i32, i32) = (INT.parsed_arg(&elems, 0), INT.parsed_arg(&elems, 1));
+        if min < max {
+            new(RandomInt::new(min, max))
+        } else {
+            Err(GenError::new_with_in_parser("the lower bound of the int should be less than the upper bound"))
+        }
     }))(i)
 }
 
-fn random_int(i: &str) -> IResult<&str, Generator> {
-    fn get_or_def(elems: &[&str], idx: usize, def: i32) -> i32 {
-        if let Some(Ok(v)) = elems
-            .get(idx)
-            .map(|s| if s.is_empty() { Ok(def) } else { s.parse() }) { v } else { def }
-    }
+const FLOAT: Signature = Signature {
+    name: "float",
+    description: "the random float between the bounds rounded to the number of decimal places",
+    params: &[
+        Param::with_default("min", "0", "the lower bound"),
+        Param::with_default("max", "1000", "the upper bound"),
+        Param::with_default("precision", "2", "the maximal number of decimal places, the trailing zeros are not kept since the float is the json number (1.50 is 1.5)"),
+    ],
+    example: "float(-1.5,1.5,3)",
+};
 
-    func("int", args_string(|elems| {
-        new({
-            let lower = get_or_def(&elems, 0, 0);
-            let upper = get_or_def(&elems, 1, 1000);
-            RandomInt::new(lower, upper)
-        })
+fn random_float(i: &str) -> Res<'_, Generator> {
+    func(FLOAT.name, args_string(|elems| {
+        let (min, max): (f64, f64) = (FLOAT.parsed_arg(&elems, 0), FLOAT.parsed_arg(&elems, 1));
+        if min < max {
+            new(RandomFloat::new(min, max, FLOAT.parsed_arg(&elems, 2)))
+        } else {
+            Err(GenError::new_with_in_parser("the lower bound of the float should be less than the upper bound"))
+        }
     }))(i)
 }
 
 
-fn random_str_from_list(i: &str) -> IResult<&str, Generator> {
-    func("str_from_list",
-         args_string(|elems| {
-             let values =
+/// creates the list either of the plain values or of the weighted values.
+/// The values should be either all weighted or none of them.
+fn from_list<T: Into<Value> + Clone + Send + 'static>(elems: Vec<(T, Option<&str>)>) -> Result<Generator, GenError> {
+    if elems.iter().all(|(_, w)| w.is_none()) {
+        new(RandomFromList::new(elems.into_iter().map(|(v, _)| v).collect()))
+    } else if elems.iter().all(|(_, w)| w.is_some()) {
+        let mut values = vec![];
+        for (v, w) in elems.into_iter() {
+            let w = w.unwrap_or_default();
+            let weight: f64 = FromStringTo::parse(w, true)
+                .map_err(|_| GenError::new_with_in_parser(
+                    format!("the weight '{}' should be a number", w).as_str()))?;
+            values.push((v, weight))
+        }
+        new(RandomFromList::new_weighted(values)?)
+    } else {
+        Err(GenError::new_with_in_parser("either all values in the list should have weights or none of them"))
+    }
+}
+
+const STR_FROM_LIST: Signature = Signature {
+    name: "str_from_list",
+    description: "the random string from the list, the weighted values are picked with the probability proportional to the weight",
+    params: &[
+        Param::required("values", "the values either all with the weights like value:weight or none, the weighted values having colons are quoted"),
+    ],
+    example: "str_from_list(NEW:70, PAID:25, CANCELLED:5)",
+};
+
+fn random_str_from_list(i: &str) -> Res<'_, Generator> {
+    func(STR_FROM_LIST.name,
+         args(|elems: Vec<(Cow<str>, Option<&str>, Option<&str>)>| {
+             let all_weighted = elems.iter().all(|(_, w, _)| w.is_some());
+             from_list(
                  elems
                      .into_iter()
-                     .map(|e| e.trim())
-                     .map(String::from)
-                     .collect();
-             new(RandomFromList::new(values))
-         }))(i)
+                     .map(|(e, w, plain)| match plain {
+                         Some(p) if !all_weighted => (String::from(p.trim()), None),
+                         _ => (String::from(e.trim()), w)
+                     })
+                     .collect())
+         }, weighted_string))(i)
+}
+
+const INT_FROM_LIST: Signature = Signature {
+    name: "int_from_list",
+    description: "the random integer from the list, the values can be weighted like in str_from_list",
+    params: &[
+        Param::required("values", "the integers either all with the weights like value:weight or none"),
+    ],
+    example: "int_from_list(1,2,3)",
+};
+
+fn random_int_from_list(i: &str) -> Res<'_, Generator> {
+    func(INT_FROM_LIST.name,
+         args(from_list, weighted(str_to_int)))(i)
+}
+
+const FLOAT_FROM_LIST: Signature = Signature {
+    name: "float_from_list",
+    description: "the random float from the list, the values can be weighted like in str_from_list",
+    params: &[
+        Param::required("values", "the floats either all with the weights like value:weight or none"),
+    ],
+    example: "float_from_list(1.5:1,2.25:3)",
+};
+
+fn random_float_from_list(i: &str) -> Res<'_, Generator> {
+    func(FLOAT_FROM_LIST.name,
+         args(from_list, weighted(str_to_float)))(i)
+}
+
+const ARRAY: Signature = Signature {
+    name: "array",
+    description: "the array filled with the values of the previous generator in the chain, the length is fixed or random from the size to the max",
+    params: &[
+        Param::with_default("size", "1", "the length or the minimal length of the array"),
+        Param::optional("max", "the maximal length of the array inclusively"),
+    ],
+    example: "int(1,10) -> array(2,5)",
+};
+
+fn random_array_empty(i: &str) -> Res<'_, Generator> {
+    func(ARRAY.name, args(|elems| {
+        match elems[..] {
+            [] => new(RandomArray::new_size(ARRAY.default_value(0).unwrap_or_default())),
+            [len] if len >= 0 => new(RandomArray::new_size(len as usize)),
+            [min, max] if min >= 0 && min <= max => new(RandomArray::new_range_size(min as usize, max as usize)),
+            _ => Err(GenError::new_with_in_parser("the array length should be a positive number or a range min,max"))
+        }
+    }, str_to_int))(i)
+}
+
+/// the flag of the file sources signalling every value in the file has a weight: `value:weight`
+const WEIGHTED: &str = "weighted";
+
+/// creates the list of the values of the file, the arguments are the path, the delimiter and the flag weighted.
+fn from_file<T>(signature: &Signature, elems: Vec<&str>) -> Result<Generator, GenError>
+    where T: Clone + FromStringTo + Into<Value> + Send + 'static {
+    let delimiter = signature.arg(&elems, 1);
+    match elems[..] {
+        [path] | [path, _] | [path, _, ""] => new(RandomFromFile::<T>::new(path, delimiter)?),
+        [path, _, WEIGHTED] => new(RandomFromFile::<T>::new_weighted(path, delimiter)?),
+        _ => Err(GenError::new_with_in_parser("the path or the delimiter should be presented"))
+    }
+}
+
+const STR_FROM_FILE: Signature = Signature {
+    name: "str_from_file",
+    description: "the random string from the values of the file",
+    params: &[
+        Param::required("path", "the path to the file"),
+        Param::with_default("delimiter", ",", "the delimiter of the values like ; or \\n"),
+        Param::optional("weighted", "the flag signalling every value in the file has the weight like value:weight"),
+    ],
+    example: "str_from_file(jsons/cities,\\n)",
+};
+
+fn random_str_from_file(i: &str) -> Res<'_, Generator> {
+    func(STR_FROM_FILE.name,
+         args_string(|elems| from_file::<String>(&STR_FROM_FILE, elems)))(i)
 }
 
-fn random_int_from_list(i: &str) -> IResult<&str, Generator> {
-    func("int_from_list",
-         args(|elems| {
-             new(RandomFromList::new(elems))
-         }, str_to_int))(i)
+const INT_FROM_FILE: Signature = Signature {
+    name: "int_from_file",
+    description: "the random integer from the values of the file",
+    params: &[
+        Param::required("path", "the path to the file"),
+        Param::with_default("delimiter", ",", "the delimiter of the values like ; or \\n"),
+        Param::optional("weighted", "the flag signalling every value in the file has the weight like value:weight"),
+    ],
+    example: "int_from_file(jsons/numbers,',')",
+};
+
+fn random_int_from_file(i: &str) -> Res<'_, Generator> {
+    func(INT_FROM_FILE.name,
+         args_string(|elems| from_file::<i64>(&INT_FROM_FILE, elems)))(i)
+}
+
+const FLOAT_FROM_FILE: Signature = Signature {
+    name: "float_from_file",
+    description: "the random float from the values of the file",
+    params: &[
+        Param::required("path", "the path to the file"),
+        Param::with_default("delimiter", ",", "the delimiter of the values like ; or \\n"),
+        Param::optional("weighted", "the flag signalling every value in the file has the weight like value:weight"),
+    ],
+    example: "float_from_file(jsons/floats,',')",
+};
+
+fn random_float_from_file(i: &str) -> Res<'_, Generator> {
+    func(FLOAT_FROM_FILE.name,
+         args_string(|elems| from_file::<f64>(&FLOAT_FROM_FILE, elems)))(i)
+}
+
+const REGEX: Signature = Signature {
+    name: "regex",
+    description: "the random string matching the regular expression",
+    params: &[
+        Param::required("pattern", "the regular expression, the pattern having commas is quoted"),
+        Param::with_default("max_repeat", "10", "the cap of the unbounded repetitions like *, + and {n,}"),
+    ],
+    example: "regex('[A-Z]{3}-\\d{4}')",
+};
+
+fn random_regex(i: &str) -> Res<'_, Generator> {
+    func(REGEX.name,
+         args(|elems: Vec<Cow<str>>| {
+             let elems: Vec<&str> = elems.iter().map(|e| e.as_ref()).collect();
+             match elems.first() {
+                 Some(pattern) => new(RandomRegex::new(pattern, REGEX.parsed_arg(&elems, 1))?),
+                 None => Err(GenError::new_with_in_parser("the regex should be presented"))
+             }
+         }, pattern_string))(i)
+}
+
+const NULLABLE: Signature = Signature {
+    name: "nullable",
+    description: "the value of the previous generator in the chain or null with the probability",
+    params: &[
+        Param::with_default("probability", "0.5", "the probability of null"),
+    ],
+    example: "int(1,10) -> nullable(0.2)",
+};
+
+fn nullable(i: &str) -> Res<'_, Generator> {
+    func(NULLABLE.name, args(|elems| {
+        new(Nullable::new(elems.first().copied().or_else(|| NULLABLE.default_value(0)).unwrap_or_default())?)
+    }, str_to_float))(i)
 }
 
-fn random_array_empty(i: &str) -> IResult<&str, Generator> {
-    func("array", args(|elems| {
-        let len = if let Some(idx) = elems.get(0) { *idx as usize } else { 1 as usize };
-        new(RandomArray::new_size(len))
+const OPTIONAL: Signature = Signature {
+    name: "optional",
+    description: "the value of the previous generator in the chain or the field is omitted with the probability",
+    params: &[
+        Param::with_default("probability", "0.5", "the probability to omit the field, null is given in the arrays"),
+    ],
+    example: "str(5) -> optional(0.3)",
+};
+
+fn optional(i: &str) -> Res<'_, Generator> {
+    func(OPTIONAL.name, args(|elems| {
+        new(Optional::new(elems.first().copied().or_else(|| OPTIONAL.default_value(0)).unwrap_or_default())?)
+    }, str_to_float))(i)
+}
+
+const UNIQUE: Signature = Signature {
+    name: "unique",
+    description: "the value of the previous generator in the chain which has not been generated before",
+    params: &[
+        Param::with_default("retries", "1000", "the number of attempts to get a new value before the generation fails"),
+    ],
+    example: "int(1,1000) -> unique()",
+};
+
+fn unique(i: &str) -> Res<'_, Generator> {
+    func(UNIQUE.name, args(|elems| {
+        match elems[..] {
+            [] => new(Unique::new(UNIQUE.default_value(0).unwrap_or_default())),
+            [retries] if retries >= 0 => new(Unique::new(retries as usize)),
+            _ => Err(GenError::new_with_in_parser("the unique should have the positive number of retries or nothing"))
+        }
     }, str_to_int))(i)
 }
 
-fn random_str_from_file(i: &str) -> IResult<&str, Generator> {
-    func("str_from_file",
+const REF: Signature = Signature {
+    name: "ref",
+    description: "the value generated for another field of the same json",
+    params: &[
+        Param::required("path", "the path to the field from the root (of the item inside @item), the names or the indexes are separated with dots, the field can be plain or inside the value of a generator like items.0.id"),
+    ],
+    example: "ref(items.0.id)",
+};
+
+fn reference(i: &str) -> Res<'_, Generator> {
+    func(REF.name,
          args_string(|elems| {
              match elems[..] {
-                 [path, d1, d2] if d1 == "" && d2 == "" =>
-                     new(RandomFromFile::<String>::new(path, ",")?),
-                 [path, d] =>
-                     new(RandomFromFile::<String>::new(path, d)?),
-                 _ => Err(GenError::new_with_in_parser("the path or the delimiter should be presented"))
+                 [path] if !path.trim().is_empty() => new(Reference::new(path.trim())),
+                 _ => Err(GenError::new_with_in_parser("the path to the field should be presented"))
              }
          }))(i)
 }
 
-fn random_int_from_file(i: &str) -> IResult<&str, Generator> {
-    func("int_from_file",
-         args_string(|elems| {
-             match elems[..] {
-                 [path, d1, d2] if d1 == "" && d2 == "" =>
-                     new(RandomFromFile::<i64>::new(path, ",")?),
-                 [path, d] =>
-                     new(RandomFromFile::<i64>::new(path, d)?),
-                 _ => Err(GenError::new_with_in_parser("the path or delimiter is not found"))
+const FMT: Signature = Signature {
+    name: "fmt",
+    description: "the string composed from the pattern where every placeholder {} is replaced with the value of the generator",
+    params: &[
+        Param::required("pattern", "the pattern with the placeholders {}, the pattern having commas is quoted"),
+        Param::required("generators", "the generators giving the values of the placeholders in order"),
+    ],
+    example: "fmt('user-{}@{}', seq(), str_from_list(a.com,b.com))",
+};
+
+fn format<'a>(i: &'a str, registry: &GeneratorRegistry) -> Res<'a, Generator> {
+    func(FMT.name,
+         args_with_generators(|elems| {
+             let mut elems = elems.into_iter();
+             let pattern = match elems.next() {
+                 Some(Arg::Str(pattern)) => pattern,
+                 _ => return Err(GenError::new_with_in_parser("the first argument should be a pattern"))
+             };
+             let mut gens = vec![];
+             for el in elems {
+                 match el {
+                     Arg::Gen(g) => gens.push(g),
+                     Arg::Str(s) => return Err(GenError::new_with_in_parser(
+                         format!("the argument '{}' should be a generator", s).as_str()))
+                 }
              }
-         }))(i)
+             new(Format::new(&pattern, gens)?)
+         }, |i| chain(i, registry)))(i)
 }
 
 
-pub fn generator(i: &str) -> Result<Generator, GenError> {
-    map_res(preceded(sp, separated_list0(tag("->"), atomic_generator)),
+/// parses the generator expression resolving the names of the generators through the registry.
+/// The error spots the generator which has failed, its span in the expression and the reason.
+pub fn generator(i: &str, registry: &GeneratorRegistry) -> Result<Generator, GenError> {
+    all_consuming(|i| chain(i, registry))(i)
+        .map(|e| e.1)
+        .map_err(|e| check(i, registry).into_iter().next().unwrap_or_else(|| {
+            let reason = match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => format!("the input '{}' is unexpected", e.input),
+                nom::Err::Incomplete(_) => "the expression is incomplete".to_string(),
+            };
+            GenError::new_with_in_parser(reason.as_str()).with_span(i, 0..i.len())
+        }))
+}
+
+/// the chain of the generators separated by `->`, merged into one generator.
+fn chain<'a>(i: &'a str, registry: &GeneratorRegistry) -> Res<'a, Generator> {
+    map_res(preceded(sp, separated_list0(tag("->"), |i| atomic_generator(i, registry))),
             |gens| {
                 let mut res: Result<Generator, GenError> =
                     gens
-                        .get(0)
+                        .first()
                         .cloned()
                         .ok_or_else(|| GenError::new_with_in_parser("at least one generator should exist"));
 
@@ -151,41 +576,267 @@ pub fn generator(i: &str) -> Result<Generator, GenError> {
                 res
             },
     )(i)
-        .map(|e| e.1)
-        .map_err(|e| GenError::new_with_in_parser(e.to_string().as_str()))
-}
-
-pub fn atomic_generator(i: &str) -> IResult<&str, Generator> {
-    terminated(
-        preceded(
-            sp,
-            alt((
-                sequence,
-                uuid,
-                random_string,
-                random_int,
-                current_dt,
-                random_str_from_file,
-                random_int_from_file,
-                random_str_from_list,
-                random_int_from_list,
-                random_array_empty,
-                bool
-            ))), sp)(i)
 }
 
+/// the generator function, the parser is found in the registry by the name of the function.
+pub fn atomic_generator<'a>(i: &'a str, registry: &GeneratorRegistry) -> Res<'a, Generator> {
+    let (i, _) = sp(i)?;
+    let (_, name) = take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(i)?;
+    match registry.parser(name) {
+        Some(parser) => terminated(|i| parser(i, registry), sp)(i),
+        None => Err(nom::Err::Error(ExprError::from_error_kind(i, ErrorKind::Tag))),
+    }
+}
+
+/// the parser of the generator function. It gets the expression starting from the name of the function
+/// and the registry to resolve the generators given as the arguments like in `fmt('{}', uuid())`.
+pub(crate) type GenParser = dyn for<'a> Fn(&'a str, &GeneratorRegistry) -> Res<'a, Generator> + Send + Sync;
+
+/// The generator functions which can be called in the templates.
+/// The default registry has the built-in generators, the user-defined generators are added with `register`.
+/// The generator with the name of the registered one replaces it.
+/// # Example
+/// ```rust
+/// use json_gen::generator::GeneratorRegistry;
+/// use json_gen::json_template::JsonTemplate;
+/// use json_gen::generator::{Generator, GeneratorFunc};
+/// use json_gen::error::GenError;
+/// use serde_json::{Value, json};
+///
+/// struct Constant(Value);
+///
+/// impl GeneratorFunc for Constant {
+///     fn next_value(&mut self) -> Value {
+///         self.0.clone()
+///     }
+/// }
+///
+///     let mut registry = GeneratorRegistry::default();
+///     registry.register("constant", |args| match args[..] {
+///         [value] => Ok(Generator::new(Constant(json!(value)))),
+///         _ => Err(GenError::new_with_in_parser("the constant should have one argument")),
+///     });
+///     let mut template = JsonTemplate::from_str_with_registry(r#"{"|status": "constant(active)"}"#, "|", &registry).unwrap();
+///     assert_eq!(template.next_value(), json!({"status": "active"}));
+/// ```
+#[derive(Clone)]
+pub struct GeneratorRegistry {
+    generators: Vec<Entry>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    name: String,
+    parser: Arc<GenParser>,
+    signature: Option<Signature>,
+}
+
+impl GeneratorRegistry {
+    /// the registry without generators.
+    pub fn empty() -> Self {
+        GeneratorRegistry { generators: vec![] }
+    }
+
+    /// registers the generator function getting the arguments split by commas and trimmed, like `name(a, b)`.
+    pub fn register<F>(&mut self, name: &str, factory: F) -> &mut Self
+        where F: Fn(Vec<&str>) -> Result<Generator, GenError> + Send + Sync + 'static {
+        self.register_factory(name, None, factory)
+    }
+
+    /// registers the generator function like `register` along with the description shown to the users,
+    /// the name of the function is taken from the signature.
+    pub fn register_with_signature<F>(&mut self, signature: Signature, factory: F) -> &mut Self
+        where F: Fn(Vec<&str>) -> Result<Generator, GenError> + Send + Sync + 'static {
+        self.register_factory(signature.name, Some(signature), factory)
+    }
+
+    fn register_factory<F>(&mut self, name: &str, signature: Option<Signature>, factory: F) -> &mut Self
+        where F: Fn(Vec<&str>) -> Result<Generator, GenError> + Send + Sync + 'static {
+        let label = name.to_string();
+        self.register_parser(name, signature, move |i, _| {
+            preceded(sp, preceded(
+                tag(label.as_str()),
+                func("", args_string(|elems| factory(elems.into_iter().map(str::trim).collect())))))(i)
+        })
+    }
+
+    /// registers the generator function parsed by the parser (see `GenParser`).
+    pub(crate) fn register_parser<P>(&mut self, name: &str, signature: Option<Signature>, parser: P) -> &mut Self
+        where P: for<'a> Fn(&'a str, &GeneratorRegistry) -> Res<'a, Generator> + Send + Sync + 'static {
+        let entry = Entry { name: name.to_string(), parser: Arc::new(parser), signature };
+        match self.generators.iter_mut().find(|e| e.name == name) {
+            Some(e) => *e = entry,
+            None => self.generators.push(entry),
+        }
+        self
+    }
+
+    /// the names of the generator functions in the order of registering.
+    pub fn names(&self) -> Vec<&str> {
+        self.generators.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    /// the descriptions of the generator functions in the order of registering,
+    /// the functions registered without the description are skipped.
+    pub fn signatures(&self) -> Vec<&Signature> {
+        self.generators.iter().flat_map(|e| e.signature.as_ref()).collect()
+    }
+
+    /// checks the generator function with the name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.parser(name).is_some()
+    }
+
+    /// the parser of the generator function with the name.
+    pub(crate) fn parser(&self, name: &str) -> Option<&GenParser> {
+        self.generators.iter().find(|e| e.name == name).map(|e| e.parser.as_ref())
+    }
+}
+
+/// the registry with the built-in generators.
+impl Default for GeneratorRegistry {
+    fn default() -> Self {
+        BUILT_IN.clone()
+    }
+}
+
+/// the parser of the built-in generator function which has no generators in the arguments.
+type PlainParser = fn(&str) -> Res<'_, Generator>;
+
+/// the built-in generators parsed without the registry along with their descriptions.
+const PLAIN_GENERATORS: [(Signature, PlainParser); 21] = [
+    (SEQ, sequence),
+    (UUID, uuid),
+    (STR, random_string),
+    (INT, random_int),
+    (FLOAT, random_float),
+    (DT_BETWEEN, dt_between),
+    (DT_OFFSET, dt_offset),
+    (DT, current_dt),
+    (STR_FROM_FILE, random_str_from_file),
+    (INT_FROM_FILE, random_int_from_file),
+    (FLOAT_FROM_FILE, random_float_from_file),
+    (STR_FROM_LIST, random_str_from_list),
+    (INT_FROM_LIST, random_int_from_list),
+    (FLOAT_FROM_LIST, random_float_from_list),
+    (REF, reference),
+    (REGEX, random_regex),
+    (ARRAY, random_array_empty),
+    (NULLABLE, nullable),
+    (OPTIONAL, optional),
+    (UNIQUE, unique),
+    (BOOL, bool),
+];
+
+pub(crate) static BUILT_IN: Lazy<GeneratorRegistry> = Lazy::new(|| {
+    let mut registry = GeneratorRegistry::empty();
+    for (signature, parser) in PLAIN_GENERATORS.iter() {
+        registry.register_parser(signature.name, Some(*signature), move |i, _| parser(i));
+    }
+    registry.register_parser(FMT.name, Some(FMT), format);
+    registry
+});
+
 fn new<T: GeneratorFunc + 'static>(gf: T) -> Result<Generator, GenError> {
     Ok(Generator::new(gf))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::generators::{generator, GenError};
-    use crate::generator::Generator;
+    use crate::parser::generators::{generator, GenError, BUILT_IN, GeneratorRegistry, Signature, Param, SEQ, STR};
+    use crate::generator::{Generator, Random};
+    use crate::generator::generators::RandomFromList;
+    use crate::error::ErrorCode;
     use serde_json::{Value, json};
 
     fn gen(i: &str) -> Result<Generator, GenError> {
-        generator(i)
+        generator(i, &BUILT_IN)
+    }
+
+    #[test]
+    fn registry_test() {
+        let mut registry = GeneratorRegistry::default();
+        registry
+            .register("upper", |args| match args[..] {
+                [value] if !value.is_empty() => Ok(Generator::new(RandomFromList::new(vec![value.to_uppercase()]))),
+                _ => Err(GenError::new_with_in_parser("the upper should have one value")),
+            })
+            .register("uuid", |_| Ok(Generator::new(RandomFromList::new(vec![0]))));
+        assert!(registry.contains("upper") && registry.contains("fmt"));
+        assert_eq!(registry.names().len(), BUILT_IN.names().len() + 1);
+
+        if_let!(generator(" upper( abc ) -> array(2)", &registry) => Ok(g) => assert_eq!(g.next(), json!(["ABC", "ABC"])));
+        if_let!(generator("fmt('{}-{}', upper(a), uuid())", &registry) => Ok(g) => assert_eq!(g.next(), json!("A-0")));
+        if_let!(generator("upper(a)", &BUILT_IN) => Err(e) => assert_eq!(e.code(), ErrorCode::UnknownGenerator));
+        if_let!(generator("int(1,2) -> upper()", &registry) => Err(e) => {
+            assert_eq!((e.code(), e.generator()), (ErrorCode::InvalidArguments, Some("upper")));
+            assert!(e.reason().contains("the upper should have one value"))
+        });
+        if_let!(generator("dt_between(2020-01-01 00:00:00,2020-01-02 00:00:00,%Y)", &registry)
+            => Ok(g) => assert_eq!(g.next(), json!("2020")));
+    }
+
+    #[test]
+    fn signature_test() {
+        assert_eq!(BUILT_IN.signatures().len(), BUILT_IN.names().len());
+        for (signature, name) in BUILT_IN.signatures().into_iter().zip(BUILT_IN.names()) {
+            assert_eq!(signature.name, name);
+            assert!(signature.example.contains(format!("{}(", name).as_str()));
+            if_let!(generator(signature.example, &BUILT_IN)
+                => Ok(g) => assert!(g.try_next().is_ok(), "{}", signature.example));
+        }
+        assert_eq!(SEQ.to_string(), "seq(start=0, step=1)");
+        assert_eq!(STR.to_string(), "str(size=0, prefix='', suffix='')");
+
+        let mut registry = GeneratorRegistry::empty();
+        const CONSTANT: Signature = Signature {
+            name: "constant",
+            description: "the same value every time",
+            params: &[Param::required("value", "the value"), Param::optional("unused", "nothing")],
+            example: "constant(a)",
+        };
+        registry.register_with_signature(CONSTANT, |_| Ok(Generator::new(RandomFromList::new(vec![1]))));
+        assert_eq!(registry.signatures(), vec![&CONSTANT]);
+        assert_eq!(CONSTANT.to_string(), "constant(value, [unused])");
+        if_let!(generator(CONSTANT.example, &registry) => Ok(g) => assert_eq!(g.next(), json!(1)));
+    }
+
+    #[test]
+    fn signature_defaults_test() {
+        // the required arguments and the generator in front of the chained ones
+        let calls = [
+            ("dt_between", "", vec!["2020-01-01", "2020-12-31"]),
+            ("dt_offset", "", vec!["-1d", "1d"]),
+            ("str_from_file", "", vec!["jsons/numbers"]),
+            ("int_from_file", "", vec!["jsons/numbers"]),
+            ("float_from_file", "", vec!["jsons/floats"]),
+            ("regex", "", vec!["'[a-z]+'"]),
+            ("array", "int(1,1000) -> ", vec![]),
+            ("nullable", "int(1,1000) -> ", vec![]),
+            ("optional", "int(1,1000) -> ", vec![]),
+            ("unique", "int(1,1000) -> ", vec![]),
+        ];
+        let values = |expr: &str| -> Vec<Value> {
+            let g = gen(expr).unwrap_or_else(|e| panic!("{}: {}", expr, e));
+            g.set_random(Random::from_seed(7));
+            (0..5).map(|_| g.next()).collect()
+        };
+        for signature in BUILT_IN.signatures().into_iter().filter(|s| s.params.iter().any(|p| p.default.is_some())) {
+            let (head, required) = calls.iter()
+                .find(|(name, _, _)| *name == signature.name)
+                .map(|(_, head, required)| (*head, required.clone()))
+                .unwrap_or(("", vec![]));
+            let defaults: Vec<String> = signature.params.iter()
+                .filter_map(|p| p.default)
+                .map(|d| if d.contains(',') { format!("'{}'", d) } else { d.to_string() })
+                .collect();
+            let omitted = format!("{}{}({})", head, signature.name, required.join(","));
+            let explicit = format!("{}{}({})", head, signature.name,
+                                   required.iter().map(|r| r.to_string()).chain(defaults).collect::<Vec<String>>().join(","));
+            // the current time can move to the next second between the calls
+            let same = (0..3).any(|_| values(omitted.as_str()) == values(explicit.as_str()));
+            assert!(same, "{} differs from {}", omitted, explicit);
+        }
     }
 
     #[test]
@@ -207,6 +858,33 @@ mod tests {
                     println!("{}",el);
                     assert_eq!(10, el.len())
                     }));
+
+        if_let!(gen("dt(epoch)") => Ok(g) => if_let!(g.next() => Value::Number(n) => assert!(n.as_i64().unwrap() > 1577836800)));
+        if_let!(gen("dt(,Asia/Tokyo)") => Ok(g) => if_let!(g.next() => Value::String(el) => assert_eq!(19, el.len())));
+        if_let!(gen("dt(%Y, Mars/Olympus)") => Err(e) => assert!(e.to_string().contains("dt(")));
+    }
+
+    #[test]
+    fn dt_between_test() {
+        if_let!(gen("dt_between(2020-01-01, 2024-12-31, %Y-%m-%d)")
+                => Ok(g)
+                => if_let!(g.next() => Value::String(el)
+                    => assert!(el.as_str() >= "2020-01-01" && el.as_str() <= "2024-12-31", "{}", el)));
+        if_let!(gen("dt_between(2020-01-01 10:00:00, 2020-01-01 10:00:00, %H:%M, America/New_York)")
+                => Ok(g) => assert_eq!(g.next(), json!("10:00")));
+        if_let!(gen("dt_between(2020-01-01, 2020-01-01 00:00:00, epoch_ms)")
+                => Ok(g) => assert_eq!(g.next(), json!(1577836800000_i64)));
+        if_let!(gen("dt_between(2020-01-01)") => Err(e) => assert!(e.to_string().contains("dt_between")));
+        if_let!(gen("dt_between(2021-01-01, 2020-01-01)") => Err(e) => assert!(e.to_string().contains("dt_between")));
+    }
+
+    #[test]
+    fn dt_offset_test() {
+        if_let!(gen("dt_offset(-30d, +2h, epoch)")
+                => Ok(g)
+                => if_let!(g.next() => Value::Number(n) => assert!(n.as_i64().unwrap() > 1577836800)));
+        if_let!(gen("dt_offset(0, 0)") => Ok(g) => if_let!(g.next() => Value::String(el) => assert_eq!(19, el.len())));
+        if_let!(gen("dt_offset(-30x, 0)") => Err(e) => assert!(e.to_string().contains("dt_offset")));
     }
 
     #[test]
@@ -304,6 +982,71 @@ mod tests {
                  assert!(n > -1 && n < 10 )
                }
         });
+        for expr in &["int(10,1)", "int(1,1)", "int(1500)"] {
+            if_let!(generator(expr, &BUILT_IN) => Err(e) => {
+                assert_eq!((e.code(), e.generator()), (ErrorCode::InvalidArguments, Some("int")));
+                assert!(e.reason().contains("the lower bound of the int should be less than the upper bound"))
+            });
+        }
+    }
+
+    #[test]
+    fn random_float_test() {
+        if_let!(gen("float(0,10,2)") => Ok(g) => {
+             for _ in (0..1000).into_iter() {
+                 let n = g.next().as_f64().unwrap();
+                 assert!((0.0..=10.0).contains(&n));
+                 assert_eq!(n, (n * 100.0).round() / 100.0);
+               }
+        });
+        if_let!(gen("float(-1.5, 1.5, 1)") => Ok(g) => {
+             for _ in (0..1000).into_iter() {
+                 let n = g.next().as_f64().unwrap();
+                 assert!((-1.5..=1.5).contains(&n));
+                 assert_eq!(n, (n * 10.0).round() / 10.0);
+               }
+        });
+        if_let!(gen("float()") => Ok(g) => {
+            for _ in (0..1000).into_iter() {
+                 let n = g.next().as_f64().unwrap();
+                 assert!((0.0..=1000.0).contains(&n));
+                 assert_eq!(n, (n * 100.0).round() / 100.0);
+               }
+        });
+        if_let!(gen("float(,,0)") => Ok(g) => {
+            let n = g.next().as_f64().unwrap();
+            assert_eq!(n, n.round());
+        });
+        for expr in &["float(10,1)", "float(1,1)", "float(1500)"] {
+            if_let!(generator(expr, &BUILT_IN) => Err(e) => {
+                assert_eq!((e.code(), e.generator()), (ErrorCode::InvalidArguments, Some("float")));
+                assert!(e.reason().contains("the lower bound of the float should be less than the upper bound"))
+            });
+        }
+    }
+
+    #[test]
+    fn random_float_from_list_test() {
+        if_let!(gen(r#"float_from_list(1.5,-2.25,3)"#) => Ok(g)
+                => {
+                let n = g.next().as_f64().unwrap();
+                assert!([1.5, -2.25, 3.0].contains(&n));
+                });
+        if_let!(gen(r#"float_from_list(a,b)"#) => Err(e)
+                => assert!(e.to_string().contains("float_from_list(a,b)")));
+    }
+
+    #[test]
+    fn random_float_from_file_test() {
+        if_let!(gen(r#"float_from_file(jsons/floats,,)"#)
+                => Ok(g)
+                => {
+                let n = g.next().as_f64().unwrap();
+                assert!(n > 0.0 && n < 10.0)
+                });
+        if_let!(gen(r#"float_from_file(jsons/cities, \n)"#)
+                => Err(e)
+                => assert!(e.to_string().contains("float_from_file")));
     }
 
     #[test]
@@ -323,6 +1066,50 @@ mod tests {
                 => assert_eq!(el,"")));
     }
 
+    #[test]
+    fn random_weighted_list_test() {
+        if_let!(gen(r#"str_from_list(NEW:70, PAID:25, CANCELLED:0)"#) => Ok(g) => {
+            for _ in 0..100 {
+                if_let!(g.next() => Value::String(el) => assert!(el == "NEW" || el == "PAID"));
+            }
+        });
+        if_let!(gen(r#"str_from_list('12:30':1, ' 13:45 ' : 0)"#) => Ok(g) => assert_eq!(g.next(), json!("12:30")));
+        if_let!(gen(r#"int_from_list(1:0,2:1.5,-3:0)"#) => Ok(g) => assert_eq!(g.next(), json!(2)));
+        if_let!(gen(r#"float_from_list(1.5:1,2.5:0)"#) => Ok(g) => assert_eq!(g.next(), json!(1.5)));
+
+        if_let!(gen(r#"str_from_list('NEW':70, PAID)"#) => Err(e) => assert!(e.to_string().contains("str_from_list")));
+        if_let!(gen(r#"str_from_list(NEW:a, PAID:25)"#) => Ok(g) => for _ in 0..20 {
+            if_let!(g.next() => Value::String(el) => assert!(el == "NEW:a" || el == "PAID:25"));
+        });
+        if_let!(gen(r#"str_from_list(NEW:70, PAID)"#) => Ok(g) => for _ in 0..20 {
+            if_let!(g.next() => Value::String(el) => assert!(el == "NEW:70" || el == "PAID"));
+        });
+        if_let!(gen(r#"int_from_list(1:0,2:0)"#) => Err(e) => assert!(e.to_string().contains("int_from_list")));
+    }
+
+    #[test]
+    fn random_list_with_colons_test() {
+        if_let!(gen(r#"str_from_list(12:30,13:45)"#) => Ok(g) => for _ in 0..20 {
+            if_let!(g.next() => Value::String(el) => assert!(el == "12:30" || el == "13:45"));
+        });
+        if_let!(gen(r#"str_from_list(12:30:00, 13:45:00)"#) => Ok(g) => for _ in 0..20 {
+            if_let!(g.next() => Value::String(el) => assert!(el == "12:30:00" || el == "13:45:00"));
+        });
+        if_let!(gen(r#"str_from_list(http://a.com,http://b.com:8080/path)"#) => Ok(g) => for _ in 0..20 {
+            if_let!(g.next() => Value::String(el) => assert!(el == "http://a.com" || el == "http://b.com:8080/path"));
+        });
+        if_let!(gen(r#"str_from_list('12:30':1, 13:45)"#) => Err(e) => assert!(e.to_string().contains("str_from_list")));
+    }
+
+    #[test]
+    fn random_weighted_file_test() {
+        if_let!(gen(r#"str_from_file(jsons/statuses, \n, weighted)"#) => Ok(g)
+                => if_let!(g.next() => Value::String(el)
+                    => assert!(["NEW", "PAID", "CANCELLED"].contains(&el.as_str()))));
+        if_let!(gen(r#"int_from_file(jsons/numbers,,weighted)"#) => Err(e)
+                => assert!(e.to_string().contains("int_from_file")));
+    }
+
     #[test]
     fn random_int_from_list_test() {
         if_let!(gen(r#"int_from_list(1,2,3)"#) => Ok(g)
@@ -377,6 +1164,120 @@ mod tests {
         if_let!(gen(r#"int_from_file()"#) => Err(el) => assert!(el.to_string().contains("int_from_file")));
     }
 
+    #[test]
+    fn random_regex_test() {
+        if_let!(gen(r#"regex('[A-Z]{3}-\d{4}')"#) => Ok(g) => if_let!(g.next() => Value::String(el) => {
+            assert_eq!(el.len(), 8);
+            assert!(el[..3].chars().all(|c| c.is_ascii_uppercase()));
+            assert!(el[4..].chars().all(|c| c.is_ascii_digit()));
+        }));
+        if_let!(gen(r#"regex('(\+7|8)-\d{3}', 3)"#) => Ok(g) => if_let!(g.next() => Value::String(el) => {
+            assert!(el.starts_with("+7-") || el.starts_with("8-"));
+        }));
+        if_let!(gen(r#"regex(a+, 2)"#) => Ok(g) => if_let!(g.next() => Value::String(el) => {
+            assert!(el == "a" || el == "aa");
+        }));
+        if_let!(gen(r#"regex('\bword')"#) => Err(e) => assert!(e.to_string().contains("regex")));
+        if_let!(gen(r#"regex()"#) => Ok(g) => assert_eq!(g.next(), json!("")));
+    }
+
+    #[test]
+    fn escaped_string_test() {
+        if_let!(gen(r#"str(0,'it\'s')"#) => Ok(g) => assert_eq!(g.next(), json!("it's")));
+        if_let!(gen(r#"str(0,'c:\path')"#) => Ok(g) => assert_eq!(g.next(), json!(r#"c:\path"#)));
+        if_let!(gen(r#"str(0,'a\\b')"#) => Ok(g) => assert_eq!(g.next(), json!(r#"a\b"#)));
+        if_let!(gen(r#"str_from_list('a\b', 'a\b')"#) => Ok(g) => assert_eq!(g.next(), json!(r#"a\b"#)));
+        if_let!(gen(r#"str(0,'\d\.')"#) => Ok(g) => assert_eq!(g.next(), json!(r#"\d\."#)));
+        if_let!(gen(r#"str(0,'\t\x41')"#) => Ok(g) => assert_eq!(g.next(), json!(r#"\t\x41"#)));
+        if_let!(gen(r#"fmt('{}\'s', str(0,'it'))"#) => Ok(g) => assert_eq!(g.next(), json!("it's")));
+
+        // the regular expressions keep the escapes but the escaped quote
+        if_let!(gen(r#"regex('\p{Lu}{2}')"#) => Ok(g) => if_let!(g.next() => Value::String(el) => {
+            assert_eq!(el.chars().count(), 2);
+            assert!(el.chars().all(char::is_uppercase))
+        }));
+        if_let!(gen(r#"regex('a\tb')"#) => Ok(g) => assert_eq!(g.next(), json!("a\tb")));
+        if_let!(gen(r#"regex('it\'s')"#) => Ok(g) => assert_eq!(g.next(), json!("it's")));
+        if_let!(gen(r#"regex('a\\b')"#) => Ok(g) => assert_eq!(g.next(), json!(r#"a\b"#)));
+        if_let!(gen(r#"regex('\/')"#) => Err(e) => assert!(e.to_string().contains(r#"the regex '\/' is invalid"#)));
+        if_let!(gen(r#"regex('\x41')"#) => Ok(g) => assert_eq!(g.next(), json!("A")));
+    }
+
+    #[test]
+    fn nullable_test() {
+        if_let!(gen("int(1,10) -> nullable(0.2)") => Ok(g) => {
+            let values: Vec<Value> = (0..1000).map(|_| g.next()).collect();
+            assert!(values.iter().any(|v| v.is_null()));
+            assert!(values.iter().any(|v| v.is_number()));
+        });
+        if_let!(gen("int(1,10) -> nullable(1) -> array(3)") => Ok(g) => assert_eq!(g.next(), json!([null, null, null])));
+        if_let!(gen("int(1,10) -> nullable()") => Ok(g) => {
+            let v = g.next();
+            assert!(v.is_null() || v.is_number())
+        });
+        if_let!(gen("int(1,10) -> nullable(2)") => Err(e) => assert!(e.to_string().contains("nullable(2)")));
+    }
+
+    #[test]
+    fn optional_test() {
+        if_let!(gen("str(5) -> optional(0.3)") => Ok(g) => {
+            let values: Vec<Option<Value>> = (0..1000).map(|_| g.next_optional()).collect();
+            assert!(values.iter().any(|v| v.is_none()));
+            assert!(values.iter().any(|v| v.is_some()));
+        });
+        if_let!(gen("str(5) -> nullable(1) -> optional(0)") => Ok(g) => assert_eq!(g.next_optional(), Some(Value::Null)));
+        if_let!(gen("str(5) -> optional(a)") => Err(e) => assert!(e.to_string().contains("optional(a)")));
+    }
+
+    #[test]
+    fn unique_test() {
+        if_let!(gen("int(1,4) -> unique()") => Ok(g) => {
+            let mut values: Vec<Value> = (0..3).map(|_| g.try_next().unwrap().unwrap()).collect();
+            values.sort_by_key(|v| v.as_i64());
+            assert_eq!(values, vec![json!(1), json!(2), json!(3)]);
+            if_let!(g.try_next() => Err(e) => assert!(e.to_string().contains("exhausted")));
+        });
+        if_let!(gen("str_from_list(a,b) -> unique(10) -> array(2)") => Ok(g) => {
+            if_let!(g.next() => Value::Array(elems) => assert_ne!(elems[0], elems[1]));
+        });
+        if_let!(gen("int(1,4) -> unique(-1)") => Err(e) => assert!(e.to_string().contains("unique(-1)")));
+    }
+
+    #[test]
+    fn reference_test() {
+        if_let!(gen("ref(record.technical.id)") => Ok(g) => {
+            assert_eq!(g.references(), vec!["record.technical.id".to_string()]);
+            g.resolve("record.technical.id", &json!(10));
+            assert_eq!(g.next(), json!(10));
+        });
+        if_let!(gen("ref( id ) -> array(2)") => Ok(g) => assert_eq!(g.references(), vec!["id".to_string()]));
+        if_let!(gen("ref()") => Err(e) => assert!(e.to_string().contains("ref()")));
+    }
+
+    #[test]
+    fn format_test() {
+        if_let!(gen("fmt('user-{}-{}@{}', seq(), str(4), str_from_list(a.com,b.com))") => Ok(g) => {
+            if_let!(g.next() => Value::String(el) => {
+                assert!(el.starts_with("user-1-"));
+                assert!(el.ends_with("@a.com") || el.ends_with("@b.com"));
+                assert_eq!(el.len(), 17);
+            });
+            if_let!(g.next() => Value::String(el) => assert!(el.starts_with("user-2-")));
+        });
+        if_let!(gen("fmt( '{}:{}' , int(1,2) -> array(2), fmt(-{}-, seq(5,5)) )") => Ok(g)
+                => assert_eq!(g.next(), json!("[1,1]:-10-")));
+        if_let!(gen("fmt(/items/{}, ref(id))") => Ok(g) => {
+            assert_eq!(g.references(), vec!["id".to_string()]);
+            g.resolve("id", &json!(1));
+            assert_eq!(g.next(), json!("/items/1"));
+        });
+        if_let!(gen("fmt(constant)") => Ok(g) => assert_eq!(g.next(), json!("constant")));
+
+        if_let!(gen("fmt('{}-{}', seq())") => Err(e) => assert!(e.to_string().contains("fmt")));
+        if_let!(gen("fmt('{}', abc)") => Err(e) => assert!(e.to_string().contains("fmt")));
+        if_let!(gen("fmt(seq())") => Err(e) => assert!(e.to_string().contains("fmt")));
+    }
+
     #[test]
     fn random_array_test() {
         if_let!(
@@ -388,7 +1289,7 @@ mod tests {
             }));
 
         if_let!(
-        generator(r#"int_from_list(1,2,3,4) -> array(3)"#) =>  Ok(g)
+        generator(r#"int_from_list(1,2,3,4) -> array(3)"#, &BUILT_IN) =>  Ok(g)
             => if_let!(g.next() => Value::Array(elems) => {
             assert!(!elems.get(0).unwrap().is_null());
             assert_eq!(elems.len(),3);
@@ -399,7 +1300,7 @@ mod tests {
             }));
 
         if_let!(
-        generator(r#"str_from_list(aaa,'bbb',ccc) -> array(3)"#) =>  Ok(g)
+        generator(r#"str_from_list(aaa,'bbb',ccc) -> array(3)"#, &BUILT_IN) =>  Ok(g)
             => if_let!(g.next() => Value::Array(elems) => {
             elems
             .iter()
@@ -408,7 +1309,15 @@ mod tests {
             }));
 
         if_let!(
-        generator(r#"seq(1) -> array(3) -> array()"#) =>  Ok(g)
+        generator(r#"seq(1) -> array(2,4)"#, &BUILT_IN) =>  Ok(g)
+            => for _ in 0..10 {
+                if_let!(g.next() => Value::Array(elems) => assert!((2..=4).contains(&elems.len())))
+            });
+        if_let!(generator(r#"seq(1) -> array(4,2)"#, &BUILT_IN) => Err(e) => assert!(e.to_string().contains("array(4,2)")));
+        if_let!(generator(r#"seq(1) -> array(-1)"#, &BUILT_IN) => Err(e) => assert!(e.to_string().contains("array(-1)")));
+
+        if_let!(
+        generator(r#"seq(1) -> array(3) -> array()"#, &BUILT_IN) =>  Ok(g)
             => if_let!(g.next() => Value::Array(elems) => {
             assert_eq!(elems.len(),1);
             assert!(!elems.get(0).unwrap().is_null());
@@ -422,7 +1331,7 @@ mod tests {
             }));
 
         // if_let!(
-        // generator(r#"seq(1) -> array(3) -> array() -> seq(1)"#) => Err(GenError{reason})
+        // generator(r#"seq(1) -> array(3) -> array() -> seq(1)"#, &BUILT_IN) => Err(GenError{reason})
         //     => {
         //     println!("{}",reason);
         //     assert!(reason.contains("the functions are unable to merge in the order"))
@@ -1,20 +1,68 @@
 //! The module being in charge of the parsing the generated functions.
 
-use nom::error::ParseError;
+use nom::error::{ParseError, FromExternalError, ErrorKind};
 
 use crate::generator::Generator;
 use nom::{
     branch::alt,
     bytes::complete::{escaped, tag, take_while, take_while1},
-    character::complete::{ char, one_of},
-    combinator::{ map_res},
+    character::complete::{ char, anychar},
+    combinator::{ map, map_res, opt},
     multi::separated_list0,
-    sequence::{ preceded, terminated},
+    sequence::{ pair, preceded, terminated},
     IResult,
 };
-use std::num::ParseIntError;
+use std::num::{ParseIntError, ParseFloatError};
+use std::borrow::Cow;
 use crate::error::GenError;
 pub mod generators;
+pub mod check;
+
+/// The error of the parsers keeping the error of the generator function which has rejected the arguments.
+#[derive(Debug)]
+pub struct ExprError<'a> {
+    /// the rest of the expression where the error occurred
+    pub input: &'a str,
+    pub kind: ErrorKind,
+    /// the error of the generator function if the arguments are invalid
+    pub cause: Option<Box<GenError>>,
+}
+
+/// The result of the parsers.
+pub type Res<'a, T> = IResult<&'a str, T, ExprError<'a>>;
+
+impl<'a> ParseError<&'a str> for ExprError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        ExprError { input, kind, cause: None }
+    }
+
+    fn append(_: &'a str, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    /// keeps the error of the generator function among the alternatives
+    fn or(self, other: Self) -> Self {
+        if self.cause.is_some() && other.cause.is_none() { self } else { other }
+    }
+}
+
+impl<'a> FromExternalError<&'a str, GenError> for ExprError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, e: GenError) -> Self {
+        ExprError { input, kind, cause: Some(Box::new(e)) }
+    }
+}
+
+impl<'a> FromExternalError<&'a str, ParseIntError> for ExprError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _: ParseIntError) -> Self {
+        ExprError::from_error_kind(input, kind)
+    }
+}
+
+impl<'a> FromExternalError<&'a str, ParseFloatError> for ExprError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _: ParseFloatError) -> Self {
+        ExprError::from_error_kind(input, kind)
+    }
+}
 
 
 fn sp<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
@@ -25,7 +73,7 @@ fn is_numeric_with_neg(c: char) -> bool {
     char::is_numeric(c) || c == '-'
 }
 
-fn str_to_int(i: &str) -> IResult<&str, i64> {
+fn str_to_int(i: &str) -> Res<'_, i64> {
     map_res(take_while1(is_numeric_with_neg),
             |s: &str| {
                 let res: Result<i64, ParseIntError> = s.parse();
@@ -33,19 +81,54 @@ fn str_to_int(i: &str) -> IResult<&str, i64> {
             })(i)
 }
 
-fn escaped_string(v: &str) -> IResult<&str, &str> {
+fn is_float_with_neg(c: char) -> bool {
+    is_numeric_with_neg(c) || c == '.'
+}
+
+fn str_to_float(i: &str) -> Res<'_, f64> {
+    map_res(take_while1(is_float_with_neg),
+            |s: &str| {
+                let res: Result<f64, ParseFloatError> = s.parse();
+                res
+            })(i)
+}
+
+/// the string encompassed by the single quotes, any char can be escaped with the backslash.
+/// The escapes are kept as they are, see `unescape`.
+fn escaped_string(v: &str) -> Res<'_, &str> {
     terminated(
         preceded(sp,
                  preceded(
                      char('\''),
                      escaped(
-                         take_while1(move |c| c != '\'' && c != '\\'), '\\', one_of("'")),
+                         take_while1(move |c| c != '\'' && c != '\\'), '\\', anychar),
                  ), ),
         char('\''))(v)
 
 }
 
-fn start_from_esc_string(v: &str) -> IResult<&str, &str> {
+/// replaces the escaped chars which are among the given chars with the chars themselves,
+/// the other escapes are kept as they are: `it\'s` gives `it's` for the chars `'`.
+fn unescape<'a>(v: &'a str, chars: &str) -> Cow<'a, str> {
+    if !v.contains('\\') {
+        return Cow::Borrowed(v);
+    }
+    let mut res = String::with_capacity(v.len());
+    let mut rest = v.chars();
+    while let Some(c) = rest.next() {
+        match (c, if c == '\\' { rest.next() } else { None }) {
+            (_, Some(e)) if chars.contains(e) => res.push(e),
+            (_, Some(e)) => {
+                res.push(c);
+                res.push(e)
+            }
+            (c, None) => res.push(c),
+        }
+    }
+    Cow::Owned(res)
+}
+
+fn start_from_esc_string(v: &str) -> Res<'_, &str> {
     terminated(
         preceded(sp,
                  preceded(
@@ -55,22 +138,67 @@ fn start_from_esc_string(v: &str) -> IResult<&str, &str> {
         char('\\'))(v)
 }
 
-fn string(v: &str) -> IResult<&str, &str> {
+fn string(v: &str) -> Res<'_, &str> {
     preceded(sp, take_while(move |c| c != ')' && c != ','))(v)
 }
 
+/// the quoted string where the escaped quotes and backslashes are replaced with the chars: `'it\'s'` gives `it's`.
+fn quoted_string(v: &str) -> Res<'_, Cow<'_, str>> {
+    map(alt((start_from_esc_string, escaped_string)), |s| unescape(s, "'\\"))(v)
+}
+
+/// the quoted regular expression where only the escaped quotes are replaced with the quotes,
+/// the other escapes are left for the regular expression: `'\d\''` gives `\d'`.
+fn quoted_pattern(v: &str) -> Res<'_, Cow<'_, str>> {
+    map(alt((start_from_esc_string, escaped_string)), |s| unescape(s, "'"))(v)
+}
+
+pub fn plain_string(v: &str) -> Res<'_, Cow<'_, str>> {
+    alt((quoted_string, map(string, Cow::Borrowed)))(v)
+}
+
+/// the regular expression either quoted or plain.
+pub fn pattern_string(v: &str) -> Res<'_, Cow<'_, str>> {
+    alt((quoted_pattern, map(string, Cow::Borrowed)))(v)
+}
+
+/// the string that can be followed by the weight: `value:weight`.
+/// The weight of the quoted string goes after the closing quote.
+/// The plain string is split by the colon only if it has one colon, the weight is a number and the value is not,
+/// otherwise the colon belongs to the value like in `12:30` or `http://a.com`.
+/// The last element is the whole plain string that is taken if the list turns out not to be weighted.
+fn weighted_string(v: &str) -> Res<'_, (Cow<'_, str>, Option<&str>, Option<&str>)> {
+    alt((
+        map(weighted(quoted_string), |(s, w)| (s, w, None)),
+        map(string, |s| match split_plain_weight(s) {
+            Some((value, weight)) => (Cow::Borrowed(value), Some(weight), Some(s)),
+            None => (Cow::Borrowed(s), None, Some(s)),
+        })
+    ))(v)
+}
+
+fn split_plain_weight(v: &str) -> Option<(&str, &str)> {
+    let mut parts = v.split(':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(value), Some(weight), None)
+        if weight.trim().parse::<f64>().is_ok() && value.trim().parse::<f64>().is_err() => Some((value, weight)),
+        _ => None
+    }
+}
 
-pub fn plain_string(v: &str) -> IResult<&str, &str> {
-    alt((start_from_esc_string, escaped_string, string))(v)
+/// the value that can be followed by the weight: `value:weight`
+fn weighted<'a, T, F>(value: F) -> impl FnMut(&'a str) -> Res<'a, (T, Option<&'a str>)>
+    where F: FnMut(&'a str) -> Res<'a, T> {
+    pair(terminated(value, sp), opt(preceded(char(':'), string)))
 }
 
-fn func<'a, F>(label: &'a str, extractor: F) -> impl FnMut(&'a str) -> IResult<&'a str, Generator>
-    where F: FnMut(&'a str) -> IResult<&'a str, Generator> {
+fn func<'a, F>(label: &'a str, extractor: F) -> impl FnMut(&'a str) -> Res<'a, Generator>
+    where F: FnMut(&'a str) -> Res<'a, Generator> {
     func_with_br(label, '(', ')', extractor)
 }
 
-fn func_with_br<'a, F>(label: &'a str, br_l: char, br_r: char, extractor: F) -> impl FnMut(&'a str) -> IResult<&'a str, Generator>
-    where F: FnMut(&'a str) -> IResult<&'a str, Generator> {
+fn func_with_br<'a, F>(label: &'a str, br_l: char, br_r: char, extractor: F) -> impl FnMut(&'a str) -> Res<'a, Generator>
+    where F: FnMut(&'a str) -> Res<'a, Generator> {
     preceded(sp, preceded(
         tag(label),
         preceded(
@@ -88,14 +216,32 @@ fn func_with_br<'a, F>(label: &'a str, br_l: char, br_r: char, extractor: F) ->
     ))
 }
 
-fn args_string<'a, F>(transformer: F) -> impl FnMut(&'a str) -> IResult<&'a str, Generator>
-    where F: Fn(Vec<&'a str>) -> Result<Generator, GenError> {
-    args(transformer, plain_string)
+/// The argument of a function, that can be either a plain string or another generator.
+pub enum Arg<'a> {
+    Str(Cow<'a, str>),
+    Gen(Generator),
+}
+
+fn arg<'a, G>(generator: G) -> impl FnMut(&'a str) -> Res<'a, Arg<'a>>
+    where G: FnMut(&'a str) -> Res<'a, Generator> {
+    terminated(alt((map(generator, Arg::Gen), map(plain_string, Arg::Str))), sp)
+}
+
+fn args_string<'a, F>(transformer: F) -> impl FnMut(&'a str) -> Res<'a, Generator>
+    where F: Fn(Vec<&str>) -> Result<Generator, GenError> {
+    args(move |elems: Vec<Cow<str>>| transformer(elems.iter().map(|e| e.as_ref()).collect()), plain_string)
+}
+
+fn args_with_generators<'a, F, G>(transformer: F, generator: G) -> impl FnMut(&'a str) -> Res<'a, Generator>
+    where
+        F: Fn(Vec<Arg<'a>>) -> Result<Generator, GenError>,
+        G: FnMut(&'a str) -> Res<'a, Generator> {
+    args(transformer, arg(generator))
 }
 
-fn args<'a, F, T, S>(transformer: F, elem_transformer: S) -> impl FnMut(&'a str) -> IResult<&'a str, Generator>
+fn args<'a, F, T, S>(transformer: F, elem_transformer: S) -> impl FnMut(&'a str) -> Res<'a, Generator>
     where
         F: Fn(Vec<T>) -> Result<Generator, GenError>,
-        S: Fn(&'a str) -> IResult<&'a str, T> {
+        S: FnMut(&'a str) -> Res<'a, T> {
     map_res(separated_list0(char(','), elem_transformer), transformer)
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+use std::fs::File;
+use serde_json::Value;
+use crate::sender::{Sender, Summary};
+use crate::sender::table::{Arrays, Column, ObjectLayout, Objects, columns};
+use crate::json_template::JsonTemplate;
+use crate::error::{GenError, ErrorCode};
+
+/// the struct which implements the Sender trait and allows
+/// to save the generated jsons to the csv (or tsv) file, one json per row.
+/// The nested objects are flattened into the columns with the dotted names like `record.geo.city`.
+/// The columns are taken from the json template, so every row has the same columns.
+/// The file is rewritten with the header when the sender starts.
+/// #Example
+/// ```
+///  use json_gen::sender::csv::CsvSender;
+///  use json_gen::sender::table::Arrays;
+///  use json_gen::json_template::JsonTemplate;
+///  let template = JsonTemplate::from_str(r#"{"|id": "seq()", "|tags": "str(3) -> array(2)"}"#, "|").unwrap();
+///  let sender = CsvSender::new("jsons/temp/items.tsv".to_string(), &template, Arrays::Indexed)
+///                 .with_delimiter(b'\t');
+/// ```
+pub struct CsvSender {
+    path: String,
+    columns: Vec<Column>,
+    delimiter: u8,
+    writer: Option<::csv::Writer<File>>,
+    summary: Summary,
+}
+
+impl CsvSender {
+    /// creates the sender writing the comma separated values with the columns of the template.
+    pub fn new(path: String, template: &JsonTemplate, arrays: Arrays) -> Self {
+        debug!("the csv sender with the path {} has been created successfully", path);
+        CsvSender {
+            path,
+            columns: columns(template, arrays, &ObjectLayout::all(Objects::Flatten)),
+            delimiter: b',',
+            writer: None,
+            summary: Summary::default(),
+        }
+    }
+    /// sets the delimiter of the values, e.g. `b'\t'` for tsv.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    fn error(&self, e: ::csv::Error) -> GenError {
+        GenError::new_with_in_sender(
+            format!("error occurred while writing to the csv file {}: {}", self.path, e).as_str())
+            .with_code(ErrorCode::Io)
+            .with_source(e)
+    }
+}
+
+impl Sender for CsvSender {
+    fn send(&mut self, json: &Value, _pretty: bool) -> Result<String, GenError> {
+        if self.writer.is_none() {
+            self.start()?;
+        }
+        let row: Vec<String> = self.columns.iter().map(|c| c.text(json)).collect();
+        let res = match self.writer.as_mut() {
+            Some(writer) => writer.write_record(row),
+            None => Ok(()),
+        };
+        match res {
+            Ok(_) => {
+                self.summary.sent += 1;
+                Ok(format!("the item has been saved to the csv file: {}", self.path))
+            }
+            Err(e) => {
+                self.summary.failed += 1;
+                Err(self.error(e))
+            }
+        }
+    }
+
+    /// rewrites the file with the header.
+    fn start(&mut self) -> Result<(), GenError> {
+        let mut writer = ::csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_path(self.path.as_str())
+            .map_err(|e| self.error(e))?;
+        writer.write_record(self.columns.iter().map(|c| c.name.as_str())).map_err(|e| self.error(e))?;
+        self.writer = Some(writer);
+        self.summary = Summary::default();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Summary, GenError> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush().map_err(|e| GenError::new_with_in_sender(
+                format!("error occurred while writing to the csv file {}: {}", self.path, e).as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e))?;
+        }
+        Ok(self.summary.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sender::csv::CsvSender;
+    use crate::sender::table::Arrays;
+    use crate::sender::{Sender, Summary};
+    use crate::json_template::JsonTemplate;
+    use crate::generate_to;
+    use std::fs::{read_to_string, remove_file};
+
+    #[test]
+    fn csv_test() {
+        let template = JsonTemplate::from_str(r#"{
+            "|id": "seq()",
+            "record": {"geo": {"|city": "str_from_list(Berlin,Rome)"}, "note": "say \"hi\", then go"},
+            "|tags": "str_from_list(a,b) -> array(2)"
+        }"#, "|").unwrap();
+        let file = "jsons/temp/csv_test.csv";
+        let mut outputs: Vec<Box<dyn Sender>> = vec![Box::new(CsvSender::new(file.to_string(), &template, Arrays::Json))];
+        generate_to(&mut template.fork(), 2, true, &mut outputs).unwrap();
+
+        let text = read_to_string(file).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "id,record.geo.city,record.note,tags");
+        assert!(lines[1].starts_with("1,"));
+        assert!(lines[2].starts_with("2,"));
+        assert!(lines[1].contains(r#","say ""hi"", then go","["#));
+        assert!(lines[1].ends_with(r#""]""#));
+        remove_file(file).unwrap();
+
+        let file = "jsons/temp/csv_test.tsv";
+        let mut sender = CsvSender::new(file.to_string(), &template, Arrays::Indexed).with_delimiter(b'\t');
+        sender.start().unwrap();
+        sender.send(&serde_json::json!({"id": 1, "tags": ["a"]}), false).unwrap();
+        assert_eq!(sender.finish().unwrap(), Summary::new(1, 0));
+        assert_eq!(read_to_string(file).unwrap(), "id\trecord.geo.city\trecord.note\ttags.0\ttags.1\n1\t\t\ta\t\n");
+        remove_file(file).unwrap();
+    }
+}
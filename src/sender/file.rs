@@ -1,8 +1,8 @@
 use std::fs::{metadata,  File, OpenOptions, create_dir_all};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use crate::sender::{Sender, string_from};
-use crate::error::GenError;
+use crate::sender::{Sender, Summary, S, string_from};
+use crate::error::{GenError, ErrorCode};
 use serde_json::Value;
 
 /// the struct which implements the Sender trait and allows
@@ -14,23 +14,20 @@ pub struct FolderSender {
 }
 
 impl FolderSender {
-    pub fn new(path: String) -> Self {
-        match metadata(path.clone()) {
-            Ok(m) => {
-                if !m.is_dir() {
-                    panic!(format!("the output path {} to the file should point to a folder.", path));
-                }
-            }
-            Err(e) =>
-                if !Path::new(path.as_str()).exists() {
-                    match create_dir_all(path.as_str()) {
-                        Ok(_) => (),
-                        Err(e) => panic!("error occurred while creating or open the file:{}", e.to_string()),
-                    }
-                } else { panic!("the error occurred with the output file: {}", e.to_string()) }
+    /// creates the sender, the folder is created if it is missing.
+    pub fn new(path: String) -> Result<Self, GenError> {
+        match metadata(path.as_str()) {
+            Ok(m) if !m.is_dir() => return Err(GenError::new_with_in_sender(
+                format!("the output path {} to the file should point to a folder.", path).as_str())
+                .with_code(ErrorCode::Io)),
+            Ok(_) => (),
+            Err(_) => create_dir_all(path.as_str()).map_err(|e| GenError::new_with_in_sender(
+                format!("error occurred while creating the folder {}: {}", path, e).as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e))?,
         }
         debug!("the folder sender with the path {} has been created successfully", path);
-        FolderSender { path, idx: 0 }
+        Ok(FolderSender { path, idx: 0 })
     }
 }
 
@@ -40,17 +37,17 @@ impl Sender for FolderSender {
         pb.push(self.path.as_str());
         pb.push(format!("json_{}.json", self.idx).as_str());
 
-        let mut file = OpenOptions::new()
+        let js =  string_from(json, pretty)?;
+        let res = OpenOptions::new()
             .create(true)
             .truncate(true)
             .write(true)
             .open(pb.as_path())
-            .expect("problem with a file");
-
-
-        let js =  string_from(json, pretty)?;
-        if let Err(e) = file.write_all(js.into_bytes().as_slice()) {
-            Err(GenError::new_with_in_sender(format!("error while appending to a file: {}", e.to_string()).as_str()))
+            .and_then(|mut file| file.write_all(js.into_bytes().as_slice()));
+        if let Err(e) = res {
+            Err(GenError::new_with_in_sender(format!("error while writing to the file {}: {}", pb.display(), e).as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e))
         } else {
             let res = format!("the item {} has been saved in the folder: {}", self.idx, self.path);
             self.idx += 1;
@@ -60,29 +57,72 @@ impl Sender for FolderSender {
 }
 
 
+/// The way the jsons are laid out in the file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framing {
+    /// the jsons are appended back to back as is.
+    Raw,
+    /// every json is appended on its own line (json lines). The jsons are not made pretty.
+    NdJson,
+    /// the file is rewritten with one json array having the jsons as the elements.
+    /// The brackets are written when the sender starts and finishes.
+    Array,
+}
+
+impl Framing {
+    /// `raw`, `ndjson` (or `jsonl`) and `array`.
+    pub fn new(framing: &str) -> Result<Self, GenError> {
+        match framing.trim() {
+            "raw" => Ok(Framing::Raw),
+            "ndjson" | "jsonl" => Ok(Framing::NdJson),
+            "array" => Ok(Framing::Array),
+            f => Err(GenError::new_with_in_sender(
+                format!("the file format '{}' is unknown, it should be raw, ndjson or array", f).as_str()))
+        }
+    }
+}
+
 /// the struct which implements the Sender trait and allows
 /// to append a generated json to file
 pub struct FileSender {
-    path: String
+    path: String,
+    framing: Framing,
+    /// the totals of the jsons saved to the file.
+    summary: Summary,
 }
 
 
 impl FileSender {
-    pub fn new(path: String) -> Self {
-        match metadata(path.clone()) {
-            Ok(m) => {
-                if m.is_dir() {
-                    panic!(format!("the output path {} should point to a file not to a folder.", path));
-                }
-            }
-            Err(_) => match create_file(path.as_str()) {
-                Ok(_) => (),
-                Err(str) => panic!("the error: {} while creating a file {}",str.to_string(), path),
-            }
+    /// creates the sender, the file is created if it is missing.
+    pub fn new(path: String) -> Result<Self, GenError> {
+        match metadata(path.as_str()) {
+            Ok(m) if m.is_dir() => return Err(GenError::new_with_in_sender(
+                format!("the output path {} should point to a file not to a folder.", path).as_str())
+                .with_code(ErrorCode::Io)),
+            Ok(_) => (),
+            Err(_) => create_file(path.as_str())?,
         }
 
         debug!("the file sender with the path {} has been created successfully", path);
-        FileSender { path }
+        Ok(FileSender { path, framing: Framing::Raw, summary: Summary::default() })
+    }
+
+    /// sets the way the jsons are laid out in the file, the jsons are appended as is by default.
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    fn write(&self, text: &str, truncate: bool) -> Result<(), GenError> {
+        let mut options = OpenOptions::new();
+        if truncate { options.write(true).truncate(true) } else { options.append(true) };
+        options
+            .open(self.path.as_str())
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| GenError::new_with_in_sender(
+                format!("error occurred while appending to the file: {}", e).as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e))
     }
 }
 
@@ -91,37 +131,61 @@ fn create_file(path: &str) -> Result<(), GenError> {
         match File::create(path) {
             Ok(_) => Ok(()),
             Err(e) => Err(GenError::new_with_in_sender(
-                format!("error occurred while creating or open the file:{}", e.to_string())
-                    .as_str())),
+                format!("error occurred while creating the file {}: {}", path, e)
+                    .as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e)),
         }
     } else { Ok(()) }
 }
 impl Sender for FileSender {
     fn send(&mut self, json: &Value, pretty: bool) -> Result<String, GenError> {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open(self.path.as_str())
-            .expect("the file to append should be there");
+        let res = match self.framing {
+            Framing::Raw => string_from(json, pretty).and_then(|js| self.write(js.as_str(), false)),
+            Framing::NdJson => self.write(format!("{}{}", json, S).as_str(), false),
+            Framing::Array => {
+                // the jsons which have failed to be written leave no trace in the file
+                let delimiter = if self.summary.sent == 0 { "" } else { "," };
+                string_from(json, pretty).and_then(|js| self.write(format!("{}{}{}", delimiter, S, js).as_str(), false))
+            }
+        };
+        match res {
+            Ok(_) => {
+                self.summary.sent += 1;
+                Ok(format!("the item has been saved to the file: {}", self.path))
+            }
+            Err(e) => {
+                self.summary.failed += 1;
+                Err(e)
+            }
+        }
+    }
 
-        let js = string_from(json, pretty)?;
-        if let Err(e) = file.write_all(js.into_bytes().as_slice()) {
-            Err(GenError::new_with_in_sender(format!("error occurred while appending to the file: {}", e.to_string())
-                .as_str()))
-        } else {
-            Ok(format!("the item has been saved to the file: {}", self.path))
+    fn start(&mut self) -> Result<(), GenError> {
+        self.summary = Summary::default();
+        if self.framing == Framing::Array { self.write("[", true) } else { Ok(()) }
+    }
+
+    fn finish(&mut self) -> Result<Summary, GenError> {
+        match self.framing {
+            Framing::Array if self.summary.sent == 0 => self.write("]", false)?,
+            Framing::Array => self.write(format!("{}]", S).as_str(), false)?,
+            _ => ()
         }
+        Ok(self.summary.clone())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::sender::file::{FileSender, FolderSender};
-    use crate::sender::Sender;
+    use crate::sender::file::{FileSender, FolderSender, Framing};
+    use crate::sender::{Sender, Summary};
     use serde_json::Value;
-    use crate::error::GenError;
+    use crate::error::{GenError, ErrorCode};
+    use std::error::Error;
     use std::path::Path;
-    use std::fs::{remove_file, remove_dir};
+    use std::fs::{remove_file, remove_dir, read_to_string};
+    use serde_json::json;
 
     fn rem_file(path: &str) -> Result<(), GenError> {
         if !Path::new(path).exists() {
@@ -152,22 +216,80 @@ mod tests {
     fn file_sender_test() {
         let file = "jsons/temp/file.json".to_string();
 
-        match FileSender::new(file.clone())
-            .send(&Value::Null, false) {
+        match FileSender::new(file.clone()).and_then(|mut s| s.send(&Value::Null, false)) {
             Ok(_) => assert!(rem_file(file.as_str()).is_ok()),
             Err(e) => panic!("error : {}", e),
         }
     }
 
+    #[test]
+    fn framing_test() {
+        let values = [json!({"id": 1}), json!({"id": 2, "tags": ["a", "b"]})];
+        for (framing, pretty) in [(Framing::NdJson, true), (Framing::Array, false), (Framing::Array, true)] {
+            let file = format!("jsons/temp/framing_{:?}_{}.json", framing, pretty);
+            std::fs::write(file.as_str(), "the previous content\n").unwrap();
+            let mut sender = FileSender::new(file.clone()).unwrap().with_framing(framing);
+            sender.start().unwrap();
+            for v in values.iter() {
+                sender.send(v, pretty).unwrap();
+            }
+            assert_eq!(sender.finish().unwrap(), Summary::new(2, 0));
+
+            let text = read_to_string(file.as_str()).unwrap();
+            if framing == Framing::NdJson {
+                assert!(text.starts_with("the previous content"));
+                let lines: Vec<Value> = text.lines().skip(1).map(|l| serde_json::from_str(l).unwrap()).collect();
+                assert_eq!(lines, values.to_vec());
+            } else {
+                assert_eq!(serde_json::from_str::<Value>(text.as_str()).unwrap(), Value::Array(values.to_vec()));
+            }
+            assert!(rem_file(file.as_str()).is_ok());
+        }
+
+        let file = "jsons/temp/framing_empty.json".to_string();
+        let mut sender = FileSender::new(file.clone()).unwrap().with_framing(Framing::Array);
+        sender.start().unwrap();
+        assert_eq!(sender.finish().unwrap(), Summary::default());
+        assert_eq!(read_to_string(file.as_str()).unwrap(), "[]");
+        assert!(rem_file(file.as_str()).is_ok());
+
+        // the json which has failed to be written is not followed by the delimiter
+        let file = "jsons/temp/framing_failed.json".to_string();
+        let mut sender = FileSender::new(file.clone()).unwrap().with_framing(Framing::Array);
+        sender.start().unwrap();
+        assert!(rem_file(file.as_str()).is_ok());
+        assert!(sender.send(&values[0], false).is_err());
+        std::fs::write(file.as_str(), "[").unwrap();
+        sender.send(&values[1], false).unwrap();
+        assert_eq!(sender.finish().unwrap(), Summary::new(1, 1));
+        assert_eq!(serde_json::from_str::<Value>(read_to_string(file.as_str()).unwrap().as_str()).unwrap(), json!([values[1]]));
+        assert!(rem_file(file.as_str()).is_ok());
+
+        let mut sender = FileSender::new(file.clone()).unwrap().with_framing(Framing::Array);
+        sender.start().unwrap();
+        assert!(rem_file(file.as_str()).is_ok());
+        assert!(sender.send(&values[0], false).is_err());
+        std::fs::write(file.as_str(), "[").unwrap();
+        assert_eq!(sender.finish().unwrap(), Summary::new(0, 1));
+        assert_eq!(read_to_string(file.as_str()).unwrap(), "[]");
+        assert!(rem_file(file.as_str()).is_ok());
+
+        if_let!(Framing::new("csv") => Err(e) => assert!(e.to_string().contains("the file format 'csv' is unknown")));
+        assert_eq!(Framing::new("jsonl").unwrap(), Framing::NdJson);
+    }
+
     #[test]
     fn folder_sender_test() {
         let file = "jsons/temp/temp".to_string();
-        match FolderSender::new(file.clone()).send(&Value::Null, false) {
+        match FolderSender::new(file.clone()).and_then(|mut s| s.send(&Value::Null, false)) {
             Ok(_) => {
                 assert!(rem_file(format!("{}/{}", file, "json_0.json").as_str()).is_ok());
                 assert!(rem_folder(file.as_str()).is_ok());
             }
             Err(e) => panic!("error : {}", e),
         }
+        assert_eq!(FolderSender::new("jsons/json.json".to_string()).err().map(|e| e.code()), Some(ErrorCode::Io));
+        assert_eq!(FileSender::new("jsons".to_string()).err().map(|e| e.code()), Some(ErrorCode::Io));
+        assert!(FileSender::new("jsons/missing/file.json".to_string()).err().and_then(|e| e.source().map(|s| s.is::<std::io::Error>())).unwrap_or(false));
     }
 }
\ No newline at end of file
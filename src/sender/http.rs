@@ -1,9 +1,229 @@
 use std::process::{Command, Output};
 use std::io;
-use crate::sender::{Sender, S, string_from};
-use crate::error::GenError;
+use std::time::Duration;
+use std::sync::{Arc, Mutex, PoisonError, mpsc};
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::thread::JoinHandle;
+use curl::easy::{Easy, List};
+use crate::sender::{Sender, Summary, BatchResult, S, string_from};
+use crate::error::{GenError, ErrorCode};
 use serde_json::Value;
 
+/// the struct which implements the Sender trait and allows
+/// to send a json to the server over http without the external curl utility.
+/// The connection to the server is reused between the jsons.
+/// The responses having the status other than 2xx are turned into the errors.
+///
+/// Having the batch size, the jsons are sent as the json array of the given size in one request.
+/// Having the concurrency, the requests are sent from the given number of workers at once
+/// and the errors are logged by the workers since the requests are not awaited.
+/// The queued requests are awaited when the sender finishes or is dropped.
+/// #Example
+/// ```
+///  use json_gen::sender::http::HttpSender;
+///  use std::time::Duration;
+///  let sender = HttpSender::new("http://127.0.0.1:7878")
+///                 .with_method("PUT")
+///                 .with_header("Authorization: Bearer token")
+///                 .with_timeout(Duration::from_secs(5))
+///                 .with_batch_size(100)
+///                 .with_concurrency(4);
+/// ```
+pub struct HttpSender {
+    request: Request,
+    batch_size: usize,
+    concurrency: usize,
+    /// the handle keeping the connection alive between the jsons.
+    handle: Easy,
+    /// the queue of the request bodies and the workers sending them, if the concurrency is greater than 1.
+    workers: Option<Workers>,
+    /// the totals shared with the workers.
+    summary: Arc<Mutex<Summary>>,
+}
+
+/// the queue of the request bodies with the number of jsons in them and the threads taking them from the queue.
+type Workers = (SyncSender<(Vec<u8>, usize)>, Vec<JoinHandle<()>>);
+
+/// the parameters of the request which are shared between the workers.
+#[derive(Clone)]
+struct Request {
+    url: String,
+    method: String,
+    headers: Vec<String>,
+    timeout: Duration,
+}
+
+impl Request {
+    fn perform(&self, handle: &mut Easy, body: &[u8]) -> Result<(u32, Vec<u8>), curl::Error> {
+        let mut headers = List::new();
+        if !self.headers.iter().any(|h| h.to_lowercase().starts_with("content-type:")) {
+            headers.append("Content-Type: application/json")?;
+        }
+        for h in self.headers.iter() {
+            headers.append(h)?;
+        }
+        handle.url(self.url.as_str())?;
+        handle.custom_request(self.method.as_str())?;
+        handle.http_headers(headers)?;
+        handle.timeout(self.timeout)?;
+        handle.post_fields_copy(body)?;
+
+        let mut response = vec![];
+        {
+            let mut transfer = handle.transfer();
+            transfer.write_function(|data| {
+                response.extend_from_slice(data);
+                Ok(data.len())
+            })?;
+            transfer.perform()?;
+        }
+        Ok((handle.response_code()?, response))
+    }
+
+    fn send(&self, handle: &mut Easy, body: &[u8]) -> Result<String, GenError> {
+        let (code, response) = self.perform(handle, body)
+            .map_err(|e| GenError::new_with_in_sender(
+                format!("error while sending the item to {}: {}", self.url, e).as_str()).with_source(e))?;
+        let response = String::from_utf8_lossy(response.as_slice());
+        if (200..300).contains(&code) {
+            Ok(format!("the item has been sent to {} with the status {} and the response: {}", self.url, code, response))
+        } else {
+            Err(GenError::new_with_in_sender(
+                format!("the server {} responded with the status {} and the response: {}", self.url, code, response).as_str()))
+        }
+    }
+}
+
+impl HttpSender {
+    /// creates the sender posting jsons to the url with the header `Content-Type: application/json` and the timeout of 30 seconds.
+    /// The jsons are sent one by one in one request at once.
+    pub fn new(url: &str) -> Self {
+        debug!("the http sender with the url: {} has been created successfully", url);
+        HttpSender {
+            request: Request {
+                url: url.to_string(),
+                method: "POST".to_string(),
+                headers: vec![],
+                timeout: Duration::from_secs(30),
+            },
+            batch_size: 1,
+            concurrency: 1,
+            handle: Easy::new(),
+            workers: None,
+            summary: Arc::new(Mutex::new(Summary::default())),
+        }
+    }
+    /// sets the http method
+    pub fn with_method(mut self, method: &str) -> Self {
+        self.request.method = method.trim().to_uppercase();
+        self
+    }
+    /// adds the header in the form `Name: value`.
+    /// The header `Content-Type` replaces the default one.
+    pub fn with_header(mut self, header: &str) -> Self {
+        self.request.headers.push(header.trim().to_string());
+        self
+    }
+    /// sets the timeout of the whole request
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.request.timeout = timeout;
+        self
+    }
+    /// sets the number of jsons sent as the json array in one request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+    /// sets the number of requests being sent at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// sends the body carrying the given number of jsons.
+    fn send_body(&mut self, body: String, items: usize) -> Result<String, GenError> {
+        if self.concurrency <= 1 {
+            let res = self.request.send(&mut self.handle, body.as_bytes());
+            count_sent(&self.summary, &res, items);
+            return res;
+        }
+        let (concurrency, request, summary) = (self.concurrency, &self.request, &self.summary);
+        let (queue, _) = self.workers.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::sync_channel::<(Vec<u8>, usize)>(concurrency);
+            let rx = Arc::new(Mutex::new(rx));
+            let workers = (0..concurrency).map(|_| {
+                let (rx, request, summary) = (rx.clone(), request.clone(), summary.clone());
+                thread::spawn(move || {
+                    let mut handle = Easy::new();
+                    loop {
+                        let (body, items) = match rx.lock().unwrap_or_else(PoisonError::into_inner).recv() {
+                            Ok(body) => body,
+                            Err(_) => break,
+                        };
+                        let res = request.send(&mut handle, body.as_slice());
+                        count_sent(&summary, &res, items);
+                        match res {
+                            Ok(res) => info!("sending json, success : {}", res),
+                            Err(e) => error!("sending json, error : {}", e)
+                        }
+                    }
+                })
+            }).collect();
+            (tx, workers)
+        });
+        queue.send((body.into_bytes(), items))
+            .map(|_| format!("the item has been queued to be sent to {}", self.request.url))
+            .map_err(|_| GenError::new_with_in_sender(
+                format!("the workers sending the items to {} have been stopped", self.request.url).as_str()))
+    }
+
+    /// waits until the queued requests are sent.
+    fn join(&mut self) {
+        if let Some((queue, workers)) = self.workers.take() {
+            drop(queue);
+            for w in workers {
+                if w.join().is_err() {
+                    error!("the worker sending the items to {} has failed", self.request.url)
+                }
+            }
+        }
+    }
+}
+
+fn count_sent(summary: &Mutex<Summary>, res: &Result<String, GenError>, items: usize) {
+    let mut summary = summary.lock().unwrap_or_else(PoisonError::into_inner);
+    if res.is_ok() { summary.sent += items } else { summary.failed += items }
+}
+
+impl Sender for HttpSender {
+    fn send(&mut self, json: &Value, pretty: bool) -> Result<String, GenError> {
+        let js = string_from(json, pretty)?;
+        self.send_body(js, 1)
+    }
+
+    fn send_batch(&mut self, jsons: &[Value], pretty: bool) -> BatchResult {
+        let res = string_from(&Value::Array(jsons.to_vec()), pretty).and_then(|js| self.send_body(js, jsons.len()));
+        BatchResult::all(jsons.len(), res)
+    }
+
+    fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// waits until the queued requests are sent and reports the totals including the requests sent by the workers.
+    fn finish(&mut self) -> Result<Summary, GenError> {
+        self.join();
+        Ok(self.summary.lock().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+}
+
+impl Drop for HttpSender {
+    fn drop(&mut self) {
+        self.join()
+    }
+}
+
 /// the struct which implements the Sender trait and allows
 /// to send a json to the server, using curl utility
 /// #Example
@@ -30,8 +250,8 @@ impl CurlSender {
 fn out_to_str(out: &Output) -> String {
     format!("| status:{}{} | stdout:{}{}{} | stderr:{}{}",
             out.status, S,
-            S, std::str::from_utf8(out.stdout.as_slice()).expect("the curl command should be correct"), S,
-            S, std::str::from_utf8(out.stderr.as_slice()).expect("the curl command should be correct"),
+            S, String::from_utf8_lossy(out.stdout.as_slice()), S,
+            S, String::from_utf8_lossy(out.stderr.as_slice()),
     )
 }
 
@@ -43,7 +263,7 @@ impl Sender for CurlSender {
                 format!("sending the item with the curl command: {} - input: {}{} {}",
                         S, self.cmd, S, out_to_str(&o))
             ),
-            Err(e) => Err(GenError::new_with_in_sender(e.to_string().as_str())),
+            Err(e) => Err(GenError::new_with_in_sender(e.to_string().as_str()).with_code(ErrorCode::Io).with_source(e)),
         }
     }
 }
@@ -69,8 +289,138 @@ pub fn curl(cmd: &str, json: &str) -> io::Result<Output> {
 
 #[cfg(test)]
 mod tests {
-    use crate::sender::http::curl;
+    use crate::sender::http::{curl, HttpSender};
+    use crate::sender::{Sender, Summary};
     use std::process::{Output};
+    use std::net::TcpListener;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use serde_json::json;
+
+    /// the request line, the headers and the body of the received request.
+    type Request = (String, Vec<String>, String);
+
+    /// starts the server answering the status from the path `/status/{code}` (200 by default) and the request body,
+    /// like the mock-server does. Every accepted connection passes the received requests after closing.
+    fn server() -> (String, mpsc::Receiver<Vec<Request>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || for stream in listener.incoming() {
+            let tx = tx.clone();
+            let mut stream = stream.unwrap();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut requests = vec![];
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        break;
+                    }
+                    let mut headers = vec![];
+                    let mut len = 0;
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        let header = header.trim().to_string();
+                        if header.is_empty() {
+                            break;
+                        }
+                        if let Some(l) = header.to_lowercase().strip_prefix("content-length:") {
+                            len = l.trim().parse().unwrap();
+                        }
+                        headers.push(header);
+                    }
+                    let mut body = vec![0; len];
+                    reader.read_exact(&mut body).unwrap();
+                    let body = String::from_utf8(body).unwrap();
+                    let code = line.split_whitespace().nth(1)
+                        .and_then(|p| p.strip_prefix("/status/"))
+                        .unwrap_or("200")
+                        .to_string();
+                    write!(stream, "HTTP/1.1 {} Status\r\nContent-Length: {}\r\n\r\n{}", code, body.len(), body).unwrap();
+                    requests.push((line.trim().to_string(), headers, body));
+                }
+                tx.send(requests).unwrap();
+            });
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn http_test() {
+        let (url, rx) = server();
+        let mut sender = HttpSender::new(format!("{}/items", url).as_str())
+            .with_method("put")
+            .with_header("X-Request-Source: json generator")
+            .with_timeout(Duration::from_secs(5));
+        for i in 0..3 {
+            if_let!(sender.send(&json!({"id": i}), false) => Ok(res) => assert!(res.contains(r#"{"id":"#)));
+        }
+        drop(sender);
+
+        let requests = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(requests.len(), 3);
+        for (i, (line, headers, body)) in requests.iter().enumerate() {
+            assert_eq!(line, "PUT /items HTTP/1.1");
+            assert!(headers.contains(&"Content-Type: application/json".to_string()));
+            assert!(headers.contains(&"X-Request-Source: json generator".to_string()));
+            assert_eq!(body, &json!({"id": i}).to_string());
+        }
+    }
+
+    #[test]
+    fn http_batch_test() {
+        let (url, rx) = server();
+        let mut sender = HttpSender::new(url.as_str()).with_batch_size(2);
+        assert_eq!(sender.batch_size(), 2);
+        if_let!(sender.send_batch(&[json!({"id": 1}), json!({"id": 2})], false).result => Ok(res) => assert!(res.contains("status 200")));
+        drop(sender);
+        let requests = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].2, json!([{"id": 1}, {"id": 2}]).to_string());
+
+        let (url, rx) = server();
+        let mut sender = HttpSender::new(url.as_str()).with_concurrency(3);
+        for i in 0..10 {
+            if_let!(sender.send(&json!({"id": i}), false) => Ok(res) => assert!(res.contains("queued")));
+        }
+        assert_eq!(sender.finish().unwrap(), Summary::new(10, 0));
+        drop(sender);
+        let mut bodies = vec![];
+        while let Ok(requests) = rx.recv_timeout(Duration::from_secs(1)) {
+            bodies.extend(requests.into_iter().map(|(_, _, body)| body));
+        }
+        bodies.sort();
+        let mut expected: Vec<String> = (0..10).map(|i| json!({"id": i}).to_string()).collect();
+        expected.sort();
+        assert_eq!(bodies, expected);
+    }
+
+    /// needs the mock-server running: `cd mock-server && cargo run`
+    #[test]
+    #[ignore]
+    fn mock_server_test() {
+        let mut sender = HttpSender::new("http://127.0.0.1:7878");
+        if_let!(sender.send(&json!({"id": 1}), false) => Ok(res) => assert!(res.contains("status 200")));
+        let mut sender = HttpSender::new("http://127.0.0.1:7878/status/404");
+        if_let!(sender.send(&json!({"id": 1}), false) => Err(e) => assert!(e.to_string().contains("status 404")));
+    }
+
+    #[test]
+    fn http_failed_test() {
+        let (url, _rx) = server();
+        let mut sender = HttpSender::new(format!("{}/status/500", url).as_str())
+            .with_header("Content-Type: text/plain");
+        if_let!(sender.send(&json!({"id": 1}), false)
+            => Err(e) => assert!(e.to_string().contains("status 500") && e.to_string().contains("sender error [sending]")));
+
+        let mut sender = HttpSender::new("http://127.0.0.1:1").with_timeout(Duration::from_secs(1));
+        if_let!(sender.send(&json!({"id": 1}), false) => Err(e) => assert!(e.to_string().contains("sender error [sending]")));
+        assert_eq!(sender.finish().unwrap(), Summary::new(0, 1));
+    }
 
     #[test]
     fn simple_test() {
@@ -4,14 +4,24 @@
 //! There are following implementations exist:
 //! * `ConsoleSender` sends the generated json to the console
 //! * 'CurlSender' sends the generated json to the remote server according to the given command
+//! * 'HttpSender' sends the generated json to the remote server over http without the curl utility
 //! * 'FolderSender' saves the generated jsons to the folder in the filesystem
-//! * 'FileSender' saves the generated jsons to the file, appending it
+//! * 'FileSender' saves the generated jsons to the file, appending it as is, as json lines or as one json array
+//! * 'CsvSender' saves the generated jsons to the csv or tsv file, flattening them into the columns
+//! * 'SqlSender' saves the generated jsons to the file as the sql insert statements
+//! * 'SqliteSender' inserts the generated jsons into the table of the sqlite database
 
 use serde_json::{Value, to_string_pretty};
-use crate::error::GenError;
+use std::fmt::{Display, Formatter};
+use std::io::{stdout, Write};
+use crate::error::{GenError, ErrorCode};
 
 pub mod http;
 pub mod file;
+pub mod table;
+pub mod csv;
+pub mod sql;
+pub mod sqlite;
 
 #[cfg(windows)]
 const S: &'static str = "\r\n";
@@ -21,13 +31,88 @@ const S: &str = "\n";
 /// the function that pursues to beautify the generated json, according to the flag `pretty`.
 pub fn string_from(json: &Value, pretty: bool) -> Result<String, GenError> {
     if pretty { to_string_pretty(json) } else { Ok(json.to_string()) }
-        .map_err(|e| GenError::new_with_in_sender(e.to_string().as_str()))
+        .map_err(|e| GenError::new_with_in_sender(e.to_string().as_str()).with_source(e))
 }
 
 /// The trait denoting the behaviour which is needed to be performed with the generated jsons.
 pub trait Sender {
     /// send to the pointed destination.
     fn send(&mut self, json: &Value, pretty: bool) -> Result<String, GenError>;
+
+    /// send the batch of jsons to the pointed destination and report how many jsons have been sent.
+    /// By default, the jsons are sent one by one, stopping at the first error,
+    /// the jsons sent before the error are reported as sent and the rest as failed.
+    fn send_batch(&mut self, jsons: &[Value], pretty: bool) -> BatchResult {
+        let mut res = vec![];
+        for json in jsons.iter() {
+            match self.send(json, pretty) {
+                Ok(r) => res.push(r),
+                Err(e) => return BatchResult::new(res.len(), Err(e)),
+            }
+        }
+        BatchResult::new(res.len(), Ok(res.join(S)))
+    }
+
+    /// the number of jsons the sender expects to get in one batch.
+    /// The jsons are passed to `send_batch` when it is greater than 1.
+    fn batch_size(&self) -> usize {
+        1
+    }
+
+    /// prepare the destination before the first json is sent (e.g. write the opening bracket of the json array).
+    /// It is called once when the generation starts and does nothing by default.
+    fn start(&mut self) -> Result<(), GenError> {
+        Ok(())
+    }
+
+    /// finish the sending when the generation ends (e.g. write the closing bracket of the json array)
+    /// and report the totals. It is called once after the last json has been sent.
+    /// By default, nothing is done and the empty totals are reported,
+    /// then the generation reports the totals counted from the results of sending.
+    fn finish(&mut self) -> Result<Summary, GenError> {
+        Ok(Summary::default())
+    }
+}
+
+/// The outcome of sending the batch of jsons.
+#[derive(Debug)]
+pub struct BatchResult {
+    /// the number of the jsons having been sent, the other jsons of the batch have failed.
+    pub sent: usize,
+    /// the message of sending or the error if a json has failed.
+    pub result: Result<String, GenError>,
+}
+
+impl BatchResult {
+    pub fn new(sent: usize, result: Result<String, GenError>) -> Self {
+        BatchResult { sent, result }
+    }
+
+    /// the batch which is either sent or failed as a whole.
+    pub fn all(items: usize, result: Result<String, GenError>) -> Self {
+        BatchResult { sent: if result.is_ok() { items } else { 0 }, result }
+    }
+}
+
+/// The totals reported by the sender when the generation ends.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    /// the number of the jsons having been sent.
+    pub sent: usize,
+    /// the number of the jsons having failed to be sent.
+    pub failed: usize,
+}
+
+impl Summary {
+    pub fn new(sent: usize, failed: usize) -> Self {
+        Summary { sent, failed }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} items have been sent, {} items have failed", self.sent, self.failed)
+    }
 }
 
 pub struct ConsoleSender {}
@@ -35,7 +120,10 @@ pub struct ConsoleSender {}
 impl Sender for ConsoleSender {
     fn send(&mut self, json: &Value, pretty: bool) -> Result<String, GenError> {
         debug!("send to the console");
-        println!("{}",  string_from(json, pretty)?);
+        writeln!(stdout().lock(), "{}", string_from(json, pretty)?)
+            .map_err(|e| GenError::new_with_in_sender(format!("the item can not be written to the console, {}", e).as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e))?;
         Ok("the item has been sent to the console".to_string())
     }
 }
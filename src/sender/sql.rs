@@ -0,0 +1,253 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use serde_json::Value;
+use crate::sender::{Sender, Summary, BatchResult, S};
+use crate::sender::table::{Arrays, Column, ObjectLayout, columns};
+use crate::json_template::JsonTemplate;
+use crate::error::{GenError, ErrorCode};
+
+/// The flavour of sql defining how the names and the values are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dialect {
+    /// the names in double quotes, the booleans as `TRUE` and `FALSE`.
+    Postgres,
+    /// the names in double quotes, the booleans as `1` and `0`.
+    Sqlite,
+    /// the names in backticks, the backslashes in the strings are escaped.
+    MySql,
+}
+
+impl Dialect {
+    /// `postgres`, `sqlite` and `mysql`.
+    pub fn new(dialect: &str) -> Result<Self, GenError> {
+        match dialect.trim().to_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(Dialect::Postgres),
+            "sqlite" => Ok(Dialect::Sqlite),
+            "mysql" => Ok(Dialect::MySql),
+            d => Err(GenError::new_with_in_sender(
+                format!("the sql dialect '{}' is unknown, it should be postgres, sqlite or mysql", d).as_str()))
+        }
+    }
+
+    /// quotes the name of the table or the column, the dots in the name of the table separate the schema.
+    pub fn name(&self, name: &str) -> String {
+        match self {
+            Dialect::MySql => format!("`{}`", name.replace('`', "``")),
+            _ => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    /// the literal of the value, the arrays and the objects are written as json text.
+    pub fn literal(&self, value: Option<&Value>) -> String {
+        match (value, self) {
+            (None, _) | (Some(Value::Null), _) => "NULL".to_string(),
+            (Some(Value::Bool(b)), Dialect::Sqlite) => if *b { "1" } else { "0" }.to_string(),
+            (Some(Value::Bool(b)), _) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+            (Some(Value::Number(n)), _) => n.to_string(),
+            (Some(Value::String(s)), _) => self.string(s),
+            (Some(v), _) => self.string(v.to_string().as_str()),
+        }
+    }
+
+    fn string(&self, s: &str) -> String {
+        match self {
+            Dialect::MySql => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''")),
+            _ => format!("'{}'", s.replace('\'', "''")),
+        }
+    }
+}
+
+/// the struct which implements the Sender trait and allows
+/// to save the generated jsons to the file as the sql statements `INSERT INTO <table> (...) VALUES (...);`.
+/// The columns are taken from the json template, the nested objects are either flattened
+/// into the columns with the dotted names like `geo.city` or written as json text, for all the objects or for every one of them.
+/// Having the number of rows, the jsons are inserted by the statements with several rows.
+/// The file is rewritten when the sender starts.
+/// #Example
+/// ```
+///  use json_gen::sender::sql::{SqlSender, Dialect};
+///  use json_gen::sender::table::Objects;
+///  use json_gen::json_template::JsonTemplate;
+///  let template = JsonTemplate::from_str(r#"{"|id": "seq()", "geo": {"|city": "str(5)"}}"#, "|").unwrap();
+///  let sender = SqlSender::new("jsons/temp/items.sql".to_string(), "items", &template, Objects::Json)
+///                 .with_dialect(Dialect::Sqlite)
+///                 .with_rows(100);
+/// ```
+pub struct SqlSender {
+    path: String,
+    table: String,
+    columns: Vec<Column>,
+    dialect: Dialect,
+    rows: usize,
+    writer: Option<BufWriter<File>>,
+    summary: Summary,
+}
+
+impl SqlSender {
+    /// creates the sender writing the postgres statements with one row each.
+    /// The layout of the nested objects is either `Objects` for all of them or `ObjectLayout`.
+    pub fn new(path: String, table: &str, template: &JsonTemplate, objects: impl Into<ObjectLayout>) -> Self {
+        debug!("the sql sender with the path {} and the table {} has been created successfully", path, table);
+        SqlSender {
+            path,
+            table: table.to_string(),
+            columns: columns(template, Arrays::Json, &objects.into()),
+            dialect: Dialect::Postgres,
+            rows: 1,
+            writer: None,
+            summary: Summary::default(),
+        }
+    }
+    /// sets the dialect of the statements.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+    /// sets the number of rows inserted by one statement.
+    pub fn with_rows(mut self, rows: usize) -> Self {
+        self.rows = rows.max(1);
+        self
+    }
+
+    /// the statement inserting the jsons.
+    pub fn insert(&self, jsons: &[Value]) -> String {
+        let table = self.table.split('.').map(|p| self.dialect.name(p)).collect::<Vec<String>>().join(".");
+        let names = self.columns.iter()
+            .map(|c| self.dialect.name(if c.name.is_empty() { "value" } else { c.name.as_str() }))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let rows = jsons.iter()
+            .map(|json| format!("({})", self.columns.iter()
+                .map(|c| self.dialect.literal(c.value(json)))
+                .collect::<Vec<String>>()
+                .join(", ")))
+            .collect::<Vec<String>>();
+        if rows.len() == 1 {
+            format!("INSERT INTO {} ({}) VALUES {};{}", table, names, rows[0], S)
+        } else {
+            format!("INSERT INTO {} ({}) VALUES{}  {};{}", table, names, S, rows.join(format!(",{}  ", S).as_str()), S)
+        }
+    }
+
+    fn write(&mut self, jsons: &[Value]) -> Result<String, GenError> {
+        if self.writer.is_none() {
+            self.start()?;
+        }
+        let statement = self.insert(jsons);
+        let res = match self.writer.as_mut() {
+            Some(writer) => writer.write_all(statement.as_bytes()),
+            None => Ok(()),
+        };
+        match res {
+            Ok(_) => {
+                self.summary.sent += jsons.len();
+                Ok(format!("{} items have been saved to the sql file: {}", jsons.len(), self.path))
+            }
+            Err(e) => {
+                self.summary.failed += jsons.len();
+                Err(GenError::new_with_in_sender(
+                    format!("error occurred while writing to the sql file {}: {}", self.path, e).as_str())
+                    .with_code(ErrorCode::Io)
+                    .with_source(e))
+            }
+        }
+    }
+}
+
+impl Sender for SqlSender {
+    fn send(&mut self, json: &Value, _pretty: bool) -> Result<String, GenError> {
+        self.write(std::slice::from_ref(json))
+    }
+
+    fn send_batch(&mut self, jsons: &[Value], _pretty: bool) -> BatchResult {
+        BatchResult::all(jsons.len(), self.write(jsons))
+    }
+
+    fn batch_size(&self) -> usize {
+        self.rows
+    }
+
+    /// rewrites the file.
+    fn start(&mut self) -> Result<(), GenError> {
+        let file = File::create(self.path.as_str()).map_err(|e| GenError::new_with_in_sender(
+            format!("error occurred while creating the sql file {}: {}", self.path, e).as_str())
+            .with_code(ErrorCode::Io)
+            .with_source(e))?;
+        self.writer = Some(BufWriter::new(file));
+        self.summary = Summary::default();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Summary, GenError> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush().map_err(|e| GenError::new_with_in_sender(
+                format!("error occurred while writing to the sql file {}: {}", self.path, e).as_str())
+                .with_code(ErrorCode::Io)
+                .with_source(e))?;
+        }
+        Ok(self.summary.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sender::sql::{SqlSender, Dialect};
+    use crate::sender::table::{Objects, ObjectLayout};
+    use crate::sender::{Sender, Summary};
+    use crate::json_template::JsonTemplate;
+    use crate::generate_to;
+    use std::fs::{read_to_string, remove_file};
+    use serde_json::json;
+
+    #[test]
+    fn insert_test() {
+        let template = JsonTemplate::from_str(r#"{
+            "|id": "seq()",
+            "|active": "bool()",
+            "geo": {"|city": "str(5)", "zip": "10115"},
+            "|name": "str(5)"
+        }"#, "|").unwrap();
+        let json = json!({"id": 1, "active": true, "geo": {"city": "Rome", "zip": "10115"}, "name": "O'Neil \\ co"});
+
+        let sender = SqlSender::new("".to_string(), "public.items", &template, Objects::Json);
+        assert_eq!(sender.insert(std::slice::from_ref(&json)),
+                   "INSERT INTO \"public\".\"items\" (\"active\", \"geo\", \"id\", \"name\") \
+                   VALUES (TRUE, '{\"city\":\"Rome\",\"zip\":\"10115\"}', 1, 'O''Neil \\ co');\n");
+
+        let sender = SqlSender::new("".to_string(), "items", &template, Objects::Flatten).with_dialect(Dialect::MySql);
+        assert_eq!(sender.insert(&[json.clone(), json!({"id": 2, "active": false})]),
+                   "INSERT INTO `items` (`active`, `geo.city`, `geo.zip`, `id`, `name`) VALUES\n  \
+                   (TRUE, 'Rome', '10115', 1, 'O''Neil \\\\ co'),\n  \
+                   (FALSE, NULL, NULL, 2, NULL);\n");
+
+        let sender = SqlSender::new("".to_string(), "items", &template, Objects::Flatten).with_dialect(Dialect::Sqlite);
+        assert!(sender.insert(std::slice::from_ref(&json)).contains("VALUES (1, 'Rome', '10115', 1, 'O''Neil \\ co')"));
+
+        let template = JsonTemplate::from_str(r#"{
+            "|id": "seq()",
+            "geo": {"|city": "str(5)", "zip": "10115"},
+            "meta": {"|tags": "str(3) -> array(1,2)"}
+        }"#, "|").unwrap();
+        let json = json!({"id": 1, "geo": {"city": "Rome", "zip": "10115"}, "meta": {"tags": ["a"]}});
+        let layout = ObjectLayout::all(Objects::Flatten).with_object("meta", Objects::Json);
+        let sender = SqlSender::new("".to_string(), "items", &template, layout);
+        assert_eq!(sender.insert(&[json]),
+                   "INSERT INTO \"items\" (\"geo.city\", \"geo.zip\", \"id\", \"meta\") \
+                   VALUES ('Rome', '10115', 1, '{\"tags\":[\"a\"]}');\n");
+
+        if_let!(Dialect::new("oracle") => Err(e) => assert!(e.to_string().contains("'oracle' is unknown")));
+    }
+
+    #[test]
+    fn sql_file_test() {
+        let template = JsonTemplate::from_str(r#"{"|id": "seq()"}"#, "|").unwrap();
+        let file = "jsons/temp/sql_test.sql";
+        let mut outputs: Vec<Box<dyn Sender>> =
+            vec![Box::new(SqlSender::new(file.to_string(), "items", &template, Objects::Json).with_rows(2))];
+        generate_to(&mut template.fork(), 3, false, &mut outputs).unwrap();
+        assert_eq!(read_to_string(file).unwrap(),
+                   "INSERT INTO \"items\" (\"id\") VALUES\n  (1),\n  (2);\nINSERT INTO \"items\" (\"id\") VALUES (3);\n");
+        assert_eq!(outputs[0].finish().unwrap(), Summary::new(3, 0));
+        remove_file(file).unwrap();
+    }
+}
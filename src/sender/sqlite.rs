@@ -0,0 +1,248 @@
+use rusqlite::{Connection, params_from_iter};
+use rusqlite::types::Value as SqlValue;
+use serde_json::Value;
+use crate::sender::{Sender, Summary, BatchResult};
+use crate::sender::sql::Dialect;
+use crate::sender::table::{Arrays, Column, ObjectLayout, columns};
+use crate::json_template::JsonTemplate;
+use crate::generator::ValueType;
+use crate::error::GenError;
+
+/// the struct which implements the Sender trait and allows
+/// to insert the generated jsons straight into the table of the local sqlite database.
+/// The columns are taken from the json template, the nested objects are either flattened
+/// into the columns with the dotted names like `geo.city` or written as json text, for all the objects or for every one of them.
+/// The table is created if it is missing, the types of the columns are taken from the generators of the template.
+/// The jsons are inserted in the transactions of the batch size.
+/// #Example
+/// ```
+///  use json_gen::sender::sqlite::SqliteSender;
+///  use json_gen::sender::table::{ObjectLayout, Objects};
+///  use json_gen::json_template::JsonTemplate;
+///  let template = JsonTemplate::from_str(r#"{"|id": "seq()", "geo": {"|city": "str(5)"}, "meta": {"|tag": "str(3)"}}"#, "|").unwrap();
+///  let layout = ObjectLayout::all(Objects::Flatten).with_object("meta", Objects::Json);
+///  let sender = SqliteSender::new("jsons/temp/items.db".to_string(), "items", &template, layout)
+///                 .with_batch_size(500);
+/// ```
+pub struct SqliteSender {
+    path: String,
+    table: String,
+    columns: Vec<Column>,
+    batch_size: usize,
+    connection: Option<Connection>,
+    /// the flag signalling the table has been checked and created if it was missing.
+    created: bool,
+    summary: Summary,
+}
+
+impl SqliteSender {
+    /// creates the sender inserting the jsons in the transactions of 1000 jsons.
+    /// The layout of the nested objects is either `Objects` for all of them or `ObjectLayout`.
+    pub fn new(path: String, table: &str, template: &JsonTemplate, objects: impl Into<ObjectLayout>) -> Self {
+        debug!("the sqlite sender with the path {} and the table {} has been created successfully", path, table);
+        SqliteSender {
+            path,
+            table: table.to_string(),
+            columns: columns(template, Arrays::Json, &objects.into()),
+            batch_size: 1000,
+            connection: None,
+            created: false,
+            summary: Summary::default(),
+        }
+    }
+    /// sets the number of jsons inserted in one transaction.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    fn error(&self, e: rusqlite::Error) -> GenError {
+        GenError::new_with_in_sender(
+            format!("error occurred while writing to the sqlite database {}: {}", self.path, e).as_str())
+            .with_source(e)
+    }
+
+    fn table_name(&self) -> String {
+        self.table.split('.').map(|p| Dialect::Sqlite.name(p)).collect::<Vec<String>>().join(".")
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.columns.iter()
+            .map(|c| Dialect::Sqlite.name(if c.name.is_empty() { "value" } else { c.name.as_str() }))
+            .collect()
+    }
+
+    /// the statement creating the table with the types of the columns,
+    /// the columns which values have no known type (e.g. `ref()`) are created without the type.
+    fn create(&self) -> String {
+        let columns = self.columns.iter().zip(self.column_names())
+            .map(|(c, name)| match c.value_type {
+                Some(ValueType::Bool) | Some(ValueType::Integer) => format!("{} INTEGER", name),
+                Some(ValueType::Float) => format!("{} REAL", name),
+                Some(ValueType::String) | Some(ValueType::Array) | Some(ValueType::Object) => format!("{} TEXT", name),
+                None => name,
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("CREATE TABLE IF NOT EXISTS {} ({})", self.table_name(), columns)
+    }
+
+    fn insert(&mut self, jsons: &[Value]) -> Result<(), GenError> {
+        if self.connection.is_none() {
+            self.start()?;
+        }
+        if !self.created {
+            let create = self.create();
+            self.execute(|c| c.execute(create.as_str(), []).map(|_| ()))?;
+            self.created = true;
+        }
+        let insert = format!("INSERT INTO {} ({}) VALUES ({})",
+                             self.table_name(),
+                             self.column_names().join(", "),
+                             (1..=self.columns.len()).map(|i| format!("?{}", i)).collect::<Vec<String>>().join(", "));
+        let rows: Vec<Vec<SqlValue>> = jsons.iter()
+            .map(|json| self.columns.iter().map(|c| sql_value(c.value(json))).collect())
+            .collect();
+        self.execute(|c| {
+            let tx = c.transaction()?;
+            {
+                let mut statement = tx.prepare_cached(insert.as_str())?;
+                for row in rows {
+                    statement.execute(params_from_iter(row))?;
+                }
+            }
+            tx.commit()
+        })
+    }
+
+    fn execute<F>(&mut self, f: F) -> Result<(), GenError>
+        where F: FnOnce(&mut Connection) -> Result<(), rusqlite::Error> {
+        match self.connection.as_mut().map(f) {
+            Some(Err(e)) => Err(self.error(e)),
+            _ => Ok(())
+        }
+    }
+
+    fn write(&mut self, jsons: &[Value]) -> Result<String, GenError> {
+        match self.insert(jsons) {
+            Ok(_) => {
+                self.summary.sent += jsons.len();
+                Ok(format!("{} items have been inserted into the table {} of the sqlite database: {}",
+                           jsons.len(), self.table, self.path))
+            }
+            Err(e) => {
+                self.summary.failed += jsons.len();
+                Err(e)
+            }
+        }
+    }
+}
+
+fn sql_value(value: Option<&Value>) -> SqlValue {
+    match value {
+        None | Some(Value::Null) => SqlValue::Null,
+        Some(Value::Bool(b)) => SqlValue::Integer(if *b { 1 } else { 0 }),
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Some(Value::String(s)) => SqlValue::Text(s.clone()),
+        Some(v) => SqlValue::Text(v.to_string()),
+    }
+}
+
+impl Sender for SqliteSender {
+    fn send(&mut self, json: &Value, _pretty: bool) -> Result<String, GenError> {
+        self.write(std::slice::from_ref(json))
+    }
+
+    /// inserts the jsons in one transaction.
+    fn send_batch(&mut self, jsons: &[Value], _pretty: bool) -> BatchResult {
+        BatchResult::all(jsons.len(), self.write(jsons))
+    }
+
+    fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// opens the database, the file is created if it is missing.
+    fn start(&mut self) -> Result<(), GenError> {
+        let connection = Connection::open(self.path.as_str()).map_err(|e| self.error(e))?;
+        self.connection = Some(connection);
+        self.created = false;
+        self.summary = Summary::default();
+        Ok(())
+    }
+
+    /// creates the table if nothing has been inserted and closes the database.
+    fn finish(&mut self) -> Result<Summary, GenError> {
+        if !self.created {
+            let create = self.create();
+            self.execute(|c| c.execute(create.as_str(), []).map(|_| ()))?;
+            self.created = true;
+        }
+        if let Some(connection) = self.connection.take() {
+            connection.close().map_err(|(_, e)| self.error(e))?;
+        }
+        Ok(self.summary.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sender::sqlite::SqliteSender;
+    use crate::sender::table::Objects;
+    use crate::sender::{Sender, Summary};
+    use crate::json_template::JsonTemplate;
+    use crate::generate_to;
+    use rusqlite::Connection;
+    use std::fs::remove_file;
+
+    #[test]
+    fn sqlite_test() {
+        let template = JsonTemplate::from_str(r#"{
+            "|id": "seq()",
+            "|active": "bool()",
+            "|price": "float(1,10)",
+            "geo": {"|city": "str_from_list(Berlin,Rome)", "zip": "10115"},
+            "|tags": "str(3) -> array(2)"
+        }"#, "|").unwrap();
+        let file = "jsons/temp/sqlite_test.db";
+        let mut outputs: Vec<Box<dyn Sender>> =
+            vec![Box::new(SqliteSender::new(file.to_string(), "items", &template, Objects::Flatten).with_batch_size(2))];
+        assert_eq!(generate_to(&mut template.fork(), 5, false, &mut outputs).unwrap(), 5);
+        assert_eq!(generate_to(&mut template.fork(), 1, false, &mut outputs).unwrap(), 1);
+
+        let connection = Connection::open(file).unwrap();
+        let sql: String = connection
+            .query_row("SELECT sql FROM sqlite_master WHERE name = 'items'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(sql, r#"CREATE TABLE "items" ("active" INTEGER, "geo.city" TEXT, "geo.zip" TEXT, "id" INTEGER, "price" REAL, "tags" TEXT)"#);
+        let ids: Vec<i64> = connection
+            .prepare(r#"SELECT "id" FROM "items" WHERE "geo.zip" = '10115' ORDER BY "id""#).unwrap()
+            .query_map([], |r| r.get(0)).unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5, 6]);
+        let tags: String = connection.query_row(r#"SELECT "tags" FROM "items""#, [], |r| r.get(0)).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(tags.as_str()).unwrap().as_array().unwrap().len(), 2);
+        drop(connection);
+        remove_file(file).unwrap();
+
+        let file = "jsons/temp/sqlite_empty.db";
+        let mut sender = SqliteSender::new(file.to_string(), "main.empty", &template, Objects::Json);
+        sender.start().unwrap();
+        assert_eq!(sender.finish().unwrap(), Summary::default());
+        let connection = Connection::open(file).unwrap();
+        let sql: String = connection
+            .query_row("SELECT sql FROM sqlite_master WHERE name = 'empty'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(sql, r#"CREATE TABLE "empty" ("active" INTEGER, "geo" TEXT, "id" INTEGER, "price" REAL, "tags" TEXT)"#);
+        drop(connection);
+        remove_file(file).unwrap();
+
+        let template = JsonTemplate::from_str(r#"{"|n": "int(1,10) -> nullable(1)", "|r": "ref(n)", "|dt": "dt(epoch)"}"#, "|").unwrap();
+        let sender = SqliteSender::new("jsons/temp/sqlite_types.db".to_string(), "types", &template, Objects::Flatten);
+        assert_eq!(sender.create(), r#"CREATE TABLE IF NOT EXISTS "types" ("dt" INTEGER, "n" INTEGER, "r")"#);
+    }
+}
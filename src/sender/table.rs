@@ -0,0 +1,216 @@
+//! The flattening of the generated jsons into the rows of the table with the same columns.
+//! The columns are taken from the structure of the json template, so every row has the same columns
+//! regardless of the values which have been generated.
+use std::collections::HashMap;
+use serde_json::Value;
+use crate::json_template::JsonTemplate;
+use crate::generator::{GeneratorFunc, ValueType};
+use crate::error::GenError;
+
+/// The way the arrays are laid out in the columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrays {
+    /// the array is put into one column as json text.
+    Json,
+    /// every item of the array gets its own column with the index in the name (`tags.0`, `tags.1`).
+    /// The generated arrays get the columns for the maximal number of items.
+    Indexed,
+}
+
+impl Arrays {
+    /// `json` and `indexed`.
+    pub fn new(arrays: &str) -> Result<Self, GenError> {
+        match arrays.trim() {
+            "json" => Ok(Arrays::Json),
+            "indexed" => Ok(Arrays::Indexed),
+            a => Err(GenError::new_with_in_sender(
+                format!("the layout of arrays '{}' is unknown, it should be json or indexed", a).as_str()))
+        }
+    }
+}
+
+/// The way the nested objects are laid out in the columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objects {
+    /// every field of the nested object gets its own column with the dotted name (`geo.city`).
+    Flatten,
+    /// the nested object is put into one column as json text.
+    Json,
+}
+
+impl Objects {
+    /// `flatten` and `json`.
+    pub fn new(objects: &str) -> Result<Self, GenError> {
+        match objects.trim() {
+            "flatten" => Ok(Objects::Flatten),
+            "json" => Ok(Objects::Json),
+            o => Err(GenError::new_with_in_sender(
+                format!("the layout of objects '{}' is unknown, it should be flatten or json", o).as_str()))
+        }
+    }
+}
+
+/// The layout of the nested objects of the template: the one for all the objects
+/// and the ones for the particular objects given by their dotted names like `record.geo`.
+/// #Example
+/// ```
+///  use json_gen::sender::table::{ObjectLayout, Objects};
+///  let layout = ObjectLayout::all(Objects::Flatten).with_object("record.geo", Objects::Json);
+///  assert_eq!(ObjectLayout::new("flatten,record.geo=json").unwrap(), layout);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectLayout {
+    objects: Objects,
+    by_name: HashMap<String, Objects>,
+}
+
+impl ObjectLayout {
+    /// the same layout for all the nested objects.
+    pub fn all(objects: Objects) -> Self {
+        ObjectLayout { objects, by_name: HashMap::new() }
+    }
+    /// sets the layout of the nested object with the dotted name.
+    /// The objects inside the object written as json text keep in this json text.
+    pub fn with_object(mut self, name: &str, objects: Objects) -> Self {
+        self.by_name.insert(name.to_string(), objects);
+        self
+    }
+    /// the comma separated list of the layouts: `flatten` or `json` for all the objects
+    /// and `<name>=flatten` or `<name>=json` for the particular objects, e.g. `json,record.geo=flatten`.
+    pub fn new(layout: &str) -> Result<Self, GenError> {
+        layout.split(',').try_fold(ObjectLayout::all(Objects::Json), |res, part| match part.split_once('=') {
+            Some((name, objects)) => Ok(res.with_object(name.trim(), Objects::new(objects)?)),
+            None => Ok(ObjectLayout { objects: Objects::new(part)?, ..res }),
+        })
+    }
+
+    fn of(&self, name: &str) -> Objects {
+        self.by_name.get(name).copied().unwrap_or(self.objects)
+    }
+}
+
+impl From<Objects> for ObjectLayout {
+    fn from(objects: Objects) -> Self {
+        ObjectLayout::all(objects)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(usize),
+}
+
+/// The column of the table: the dotted name (e.g. `record.geo.city`) and the path to the value in the json.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    /// the type of the values taken from the generator or the plain value of the template, if it is known.
+    pub value_type: Option<ValueType>,
+    path: Vec<Step>,
+}
+
+impl Column {
+    fn child(&self, step: Step) -> Column {
+        let key = match &step {
+            Step::Field(f) => f.clone(),
+            Step::Index(i) => i.to_string(),
+        };
+        let name = if self.name.is_empty() { key } else { format!("{}.{}", self.name, key) };
+        let mut path = self.path.clone();
+        path.push(step);
+        Column { name, value_type: None, path }
+    }
+
+    /// the value of the column in the json, if the json has it.
+    pub fn value<'a>(&self, json: &'a Value) -> Option<&'a Value> {
+        self.path.iter().try_fold(json, |v, step| match step {
+            Step::Field(f) => v.get(f.as_str()),
+            Step::Index(i) => v.get(i),
+        })
+    }
+
+    /// the text of the value in the cell: the strings as is, the arrays and the objects as json text
+    /// and nothing for the absent values and nulls.
+    pub fn text(&self, json: &Value) -> String {
+        match self.value(json) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+        }
+    }
+}
+
+/// the columns of the jsons generated by the template.
+/// The fields of the template object always get their own columns and the nested objects are laid out according to `layout`.
+/// The columns go in the order of the fields in the generated jsons.
+/// The template which is not an object gives the only column with the empty name.
+pub fn columns(template: &JsonTemplate, arrays: Arrays, layout: &ObjectLayout) -> Vec<Column> {
+    fn collect(template: &JsonTemplate, column: Column, arrays: Arrays, layout: &ObjectLayout, res: &mut Vec<Column>) {
+        match template {
+            JsonTemplate::Object(_) if !column.path.is_empty() && layout.of(&column.name) == Objects::Json =>
+                res.push(Column { value_type: Some(ValueType::Object), ..column }),
+            JsonTemplate::Object(pairs) => {
+                // the same order of the fields as in the generated jsons
+                let mut pairs: Vec<&(String, JsonTemplate)> = pairs.iter().collect();
+                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                pairs.into_iter()
+                    .for_each(|(k, t)| collect(t, column.child(Step::Field(k.clone())), arrays, layout, res))
+            }
+            JsonTemplate::Array(elems) if arrays == Arrays::Indexed =>
+                elems.iter().enumerate()
+                    .for_each(|(i, t)| collect(t, column.child(Step::Index(i)), arrays, layout, res)),
+            JsonTemplate::Gen(g) if arrays == Arrays::Indexed && g.max_len().is_some() =>
+                (0..g.max_len().unwrap_or(0)).for_each(|i| res.push(column.child(Step::Index(i)))),
+            JsonTemplate::Ordered(o) => collect(o.template(), column, arrays, layout, res),
+            _ => res.push(Column { value_type: template.value_type(), ..column }),
+        }
+    }
+    let mut res = vec![];
+    collect(template, Column { name: String::new(), value_type: None, path: vec![] }, arrays, layout, &mut res);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sender::table::{columns, Arrays, Objects, ObjectLayout};
+    use crate::json_template::JsonTemplate;
+    use serde_json::json;
+
+    #[test]
+    fn columns_test() {
+        let template = JsonTemplate::from_str(r#"{
+            "|id": "seq()",
+            "record": {"geo": {"|city": "str_from_list(Berlin,Rome)", "zip": 10115}},
+            "tags": ["a", {"|b": "bool()"}],
+            "|items": "int(1,10) -> array(0,2) -> nullable()"
+        }"#, "|").unwrap();
+        let flatten = ObjectLayout::all(Objects::Flatten);
+        let names = |arrays| columns(&template, arrays, &flatten).into_iter().map(|c| c.name).collect::<Vec<String>>();
+        assert_eq!(names(Arrays::Json), vec!["id", "items", "record.geo.city", "record.geo.zip", "tags"]);
+        assert_eq!(names(Arrays::Indexed),
+                   vec!["id", "items.0", "items.1", "record.geo.city", "record.geo.zip", "tags.0", "tags.1.b"]);
+
+        let json = json!({"id": 1, "items": [5], "record": {"geo": {"city": "Rome", "zip": 10115}}, "tags": ["a", {"b": true}]});
+        let texts = |arrays| columns(&template, arrays, &flatten).iter().map(|c| c.text(&json)).collect::<Vec<String>>();
+        assert_eq!(texts(Arrays::Json), vec!["1", "[5]", "Rome", "10115", r#"["a",{"b":true}]"#]);
+        assert_eq!(texts(Arrays::Indexed), vec!["1", "5", "", "Rome", "10115", "a", "true"]);
+
+        let json_layout = ObjectLayout::all(Objects::Json);
+        let names: Vec<String> = columns(&template, Arrays::Indexed, &json_layout).into_iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["id", "items.0", "items.1", "record", "tags.0", "tags.1"]);
+        let texts: Vec<String> = columns(&template, Arrays::Json, &json_layout).iter().map(|c| c.text(&json)).collect();
+        assert_eq!(texts[2], r#"{"geo":{"city":"Rome","zip":10115}}"#);
+
+        let names = |layout: &str| columns(&template, Arrays::Json, &ObjectLayout::new(layout).unwrap())
+            .into_iter().map(|c| c.name).collect::<Vec<String>>();
+        assert_eq!(names("flatten,record.geo=json"), vec!["id", "items", "record.geo", "tags"]);
+        assert_eq!(names("json, record = flatten"), vec!["id", "items", "record.geo", "tags"]);
+        assert_eq!(names("record=flatten,record.geo=flatten"), vec!["id", "items", "record.geo.city", "record.geo.zip", "tags"]);
+        assert_eq!(ObjectLayout::new("json").unwrap(), ObjectLayout::from(Objects::Json));
+        if_let!(ObjectLayout::new("flatten,geo=nested") => Err(e) => assert!(e.to_string().contains("'nested' is unknown")));
+
+        if_let!(Objects::new("nested") => Err(e) => assert!(e.to_string().contains("'nested' is unknown")));
+        if_let!(Arrays::new("flat") => Err(e) => assert!(e.to_string().contains("'flat' is unknown")));
+    }
+}
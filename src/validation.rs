@@ -0,0 +1,220 @@
+//! ### Validation
+//! The module checking the json template without generating anything.
+//! Every generator expression is parsed on its own, so all the mistakes of the template are reported at once,
+//! each with the path of the field, the column in the expression and the likely name of a misspelled generator.
+
+use std::fmt::{Display, Formatter};
+use serde_json::Value;
+use crate::error::{GenError, ErrorCode};
+use crate::json_template::{JsonTemplate, child_path, ITEM, REPEAT};
+use crate::parser::check::check;
+use crate::parser::generators::{generator, GeneratorRegistry, BUILT_IN};
+
+/// The mistake found in the json template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// the path of the field like `user.tags.0.name`, the indicator is omitted.
+    /// The path is empty for the mistakes of the whole template.
+    pub path: String,
+    /// the generator expression of the field if the mistake is in the expression.
+    pub expression: Option<String>,
+    /// the column in the expression, starting from 1.
+    pub column: Option<usize>,
+    pub reason: String,
+    /// the name of the generator which is likely meant instead of the unknown one.
+    pub suggestion: Option<String>,
+}
+
+impl Issue {
+    fn new(path: &str, reason: &str) -> Self {
+        Issue { path: path.to_string(), expression: None, column: None, reason: reason.to_string(), suggestion: None }
+    }
+
+    fn from_error(path: &str, e: GenError, registry: &GeneratorRegistry) -> Self {
+        let suggestion = match (e.code(), e.generator()) {
+            (ErrorCode::UnknownGenerator, Some(name)) => suggestion(name, registry).map(|s| s.to_string()),
+            _ => None,
+        };
+        let column = match (e.expression(), e.span()) {
+            (Some(expression), Some(span)) => Some(expression[..span.start].chars().count() + 1),
+            _ => None,
+        };
+        Issue {
+            path: e.path().map(|p| child_path(path, p)).unwrap_or_else(|| path.to_string()),
+            expression: e.expression().map(|e| e.to_string()),
+            column,
+            reason: e.reason().to_string(),
+            suggestion,
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let place = if self.path.is_empty() { "the template".to_string() } else { format!("the field '{}'", self.path) };
+        match self.column {
+            Some(column) => write!(f, "{} at column {}: {}", place, column, self.reason)?,
+            None => write!(f, "{}: {}", place, self.reason)?,
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean `{}`?", suggestion)?
+        }
+        if let (Some(expression), Some(column)) = (&self.expression, self.column) {
+            write!(f, "\n    {}\n    {}^", expression, " ".repeat(column - 1))?
+        }
+        Ok(())
+    }
+}
+
+/// checks the json template given as a text.
+/// Returns the found mistakes, the template is valid if there are none.
+/// # Example
+/// ```rust
+/// use json_gen::validation::validate;
+///
+///     let issues = validate(r#"{"user": {"|id": "uuidds()"}}"#, "|");
+///     assert_eq!(issues[0].path, "user.id");
+///     assert_eq!(issues[0].column, Some(1));
+///     assert_eq!(issues[0].suggestion, Some("uuid".to_string()));
+/// ```
+pub fn validate(json: &str, indicator: &str) -> Vec<Issue> {
+    validate_with_registry(json, indicator, &BUILT_IN)
+}
+
+/// checks the json template given as a text, the generators are resolved through the registry.
+pub fn validate_with_registry(json: &str, indicator: &str, registry: &GeneratorRegistry) -> Vec<Issue> {
+    match serde_json::from_str(json) {
+        Ok(value) => validate_value_with_registry(&value, indicator, registry),
+        Err(e) => vec![Issue::new("", format!("the template is not a valid json, {}", e).as_str())],
+    }
+}
+
+/// checks the json template.
+/// The references between the fields are checked only if all generator expressions are valid.
+pub fn validate_value(value: &Value, indicator: &str) -> Vec<Issue> {
+    validate_value_with_registry(value, indicator, &BUILT_IN)
+}
+
+/// checks the json template, the generators are resolved through the registry.
+pub fn validate_value_with_registry(value: &Value, indicator: &str, registry: &GeneratorRegistry) -> Vec<Issue> {
+    let mut issues = vec![];
+    check_value(value, "", indicator, registry, &mut issues);
+    if issues.is_empty() {
+        if let Err(e) = JsonTemplate::new_with_registry(value.clone(), indicator, registry) {
+            issues.push(Issue::from_error("", e, registry))
+        }
+    }
+    issues
+}
+
+fn check_value(value: &Value, path: &str, indicator: &str, registry: &GeneratorRegistry, issues: &mut Vec<Issue>) {
+    match value {
+        Value::Object(pairs) => {
+            for (k, v) in pairs.iter() {
+                match (k.strip_prefix(indicator), v) {
+                    (Some(name), Value::String(expression)) => {
+                        let mut errors = check(expression, registry);
+                        if errors.is_empty() {
+                            errors.extend(generator(expression, registry).err())
+                        }
+                        issues.extend(errors.into_iter().map(|e| Issue::from_error(&child_path(path, name), e, registry)))
+                    }
+                    (Some(name), Value::Object(directive)) if directive.contains_key(ITEM) =>
+                        check_value(&directive[ITEM], &child_path(&child_path(path, name), ITEM), indicator, registry, issues),
+                    (Some(name), _) => issues.push(Issue::new(
+                        &child_path(path, name),
+                        format!("a generator function should be a string or an object with the fields '{}' and '{}'", REPEAT, ITEM)
+                            .as_str())),
+                    (None, v) => check_value(v, &child_path(path, k), indicator, registry, issues),
+                }
+            }
+        }
+        Value::Array(elems) =>
+            elems.iter().enumerate().for_each(|(i, v)| check_value(v, &child_path(path, &i.to_string()), indicator, registry, issues)),
+        _ => ()
+    }
+}
+
+/// the closest name of the generator if it is close enough.
+fn suggestion<'a>(name: &str, registry: &'a GeneratorRegistry) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    registry.names().into_iter()
+        .map(|n| (distance(name, n), n))
+        .filter(|(d, _)| *d <= max)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, n)| n)
+}
+
+/// the Levenshtein distance between the strings.
+fn distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut prev: Vec<usize> = (0..=right.len()).collect();
+    for (i, l) in left.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, r) in right.iter().enumerate() {
+            let cost = if l == *r { 0 } else { 1 };
+            curr.push((prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+    prev[right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validation::{validate, distance};
+
+    #[test]
+    fn validate_test() {
+        assert!(validate(r#"{"|id": "seq()", "|tags": "str(3) -> array(2) -> nullable()", "|f": "fmt('{}-{}', ref(id), uuid())"}"#, "|")
+            .is_empty());
+
+        let issues = validate(r#"{"users": [{"|x": "uuidds()"}, {"|y": "int(1,2) -> arrayy(3)"}]}"#, "|");
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].path, "users.0.x");
+        assert_eq!(issues[0].column, Some(1));
+        assert_eq!(issues[0].suggestion, Some("uuid".to_string()));
+        assert_eq!(issues[1].path, "users.1.y");
+        assert_eq!(issues[1].column, Some(13));
+        assert_eq!(issues[1].suggestion, Some("array".to_string()));
+        assert_eq!(issues[1].to_string(),
+                   "the field 'users.1.y' at column 13: the generator 'arrayy' is unknown, did you mean `array`?\n    \
+                   int(1,2) -> arrayy(3)\n                \
+                   ^");
+
+        let issues = validate(r#"{"|a": "fmt('{}', zzz())", "|b": "str_from_list('a->b(c)', d)"}"#, "|");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].column, issues[0].suggestion.clone()), (Some(11), None));
+
+        let column = |expression: &str| {
+            let issues = validate(format!(r#"{{"|x": "{}"}}"#, expression).as_str(), "|");
+            assert_eq!(issues.len(), 1);
+            (issues[0].column.unwrap(), issues[0].reason.clone())
+        };
+        assert_eq!(column("int(1,2) -> array(-1)"),
+                   (19, "the arguments of the generator 'array' are invalid, \
+                   the array length should be a positive number or a range min,max".to_string()));
+        assert_eq!(column("uuid() x").0, 8);
+        assert!(column("int(1,2").1.contains("are not closed"));
+        assert!(column("uuid").1.contains("like uuid()"));
+        assert!(column("uuid() -> ").1.contains("is expected"));
+        assert_eq!(column("int() -> uuid()").0, 10);
+
+        let issues = validate(r#"{"|list": {"@repeat": 2, "@item": {"|id": "sq()"}}, "|n": 1}"#, "|");
+        assert_eq!(issues.len(), 2);
+        assert_eq!((issues[0].path.as_str(), issues[0].suggestion.clone()), ("list.@item.id", Some("seq".to_string())));
+        assert!(issues[1].reason.contains("should be a string"));
+
+        let issues = validate(r#"{"|a": "ref(b)", "|b": "ref(a)"}"#, "|");
+        assert!(issues[0].reason.contains("cyclic reference"));
+        assert_eq!(issues[0].column, None);
+        assert!(validate(r#"{"|a": "#, "|")[0].to_string().starts_with("the template: the template is not a valid json"));
+    }
+
+    #[test]
+    fn distance_test() {
+        assert_eq!(distance("uuidds", "uuid"), 2);
+        assert_eq!(distance("", "seq"), 3);
+        assert_eq!(distance("flaot", "float"), 2);
+    }
+}
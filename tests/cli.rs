@@ -40,4 +40,209 @@ fn basic_body_test() -> Result<(), Box<dyn std::error::Error>> {
         );
 
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn to_http_test() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/items", listener.local_addr()?);
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut stream = stream;
+        let mut requests = vec![];
+        for _ in 0..2 {
+            let mut request = String::new();
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() { break; }
+                if let Some(l) = line.to_lowercase().strip_prefix("content-length:") {
+                    len = l.trim().parse().unwrap();
+                }
+                request.push_str(line.as_str());
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(String::from_utf8(body).unwrap().as_str());
+            stream.write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n").unwrap();
+            requests.push(request);
+        }
+        requests
+    });
+
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg(r#"--body={"|id": "seq()"}"#)
+        .args(["-r", "2", "--to-http", url.as_str(), "--http-method", "PUT", "--http-header", "X-Source: json gen"]);
+    cmd.assert().success();
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("PUT /items HTTP/1.1"));
+    assert!(requests[0].contains("X-Source: json gen"));
+    assert!(requests[0].ends_with(r#"{"id":1}"#));
+    assert!(requests[1].ends_with(r#"{"id":2}"#));
+    Ok(())
+}
+
+#[test]
+fn to_file_format_test() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "jsons/temp/cli_array.json";
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg(r#"--body={"|id": "seq()"}"#)
+        .args(["-r", "3", "--to-file", file, "--to-file-format", "array", "--pretty"]);
+    cmd.assert().success();
+
+    let values: serde_json::Value = serde_json::from_str(std::fs::read_to_string(file)?.as_str())?;
+    std::fs::remove_file(file)?;
+    assert_eq!(values, serde_json::json!([{"id": 1}, {"id": 2}, {"id": 3}]));
+    Ok(())
+}
+
+#[test]
+fn to_csv_test() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "jsons/temp/cli_items.tsv";
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg(r#"--body={"|id": "seq()", "geo": {"city": "Rome"}, "|tags": "str_from_list(a) -> array(2)"}"#)
+        .args(["-r", "2", "--to-csv", file, "--csv-delimiter", "tab", "--csv-arrays", "indexed"]);
+    cmd.assert().success();
+
+    let text = std::fs::read_to_string(file)?;
+    std::fs::remove_file(file)?;
+    assert_eq!(text, "geo.city\tid\ttags.0\ttags.1\nRome\t1\ta\ta\nRome\t2\ta\ta\n");
+    Ok(())
+}
+
+#[test]
+fn to_sql_test() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "jsons/temp/cli_items.sql";
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg(r#"--body={"|id": "seq()", "geo": {"city": "Rome"}}"#)
+        .args(["-r", "3", "--to-sql", file, "--sql-table", "items", "--sql-dialect", "mysql",
+            "--sql-rows", "2", "--sql-objects", "flatten"]);
+    cmd.assert().success();
+
+    let text = std::fs::read_to_string(file)?;
+    std::fs::remove_file(file)?;
+    assert_eq!(text, "INSERT INTO `items` (`geo.city`, `id`) VALUES\n  ('Rome', 1),\n  ('Rome', 2);\n\
+                      INSERT INTO `items` (`geo.city`, `id`) VALUES ('Rome', 3);\n");
+    Ok(())
+}
+
+#[test]
+fn to_sqlite_test() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "jsons/temp/cli_items.db";
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg(r#"--body={"|id": "seq()", "geo": {"city": "Rome"}}"#)
+        .args(["-r", "5", "--to-sqlite", file, "--sql-table", "items", "--sqlite-batch-size", "2"]);
+    cmd.assert().success();
+
+    let connection = rusqlite::Connection::open(file)?;
+    let count: i64 = connection.query_row(r#"SELECT count(*) FROM "items" WHERE "geo" = '{"city":"Rome"}'"#, [], |r| r.get(0))?;
+    drop(connection);
+    std::fs::remove_file(file)?;
+    assert_eq!(count, 5);
+    Ok(())
+}
+
+#[test]
+fn validate_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.args(["validate", "-f", "jsons/example_template.json"]);
+    cmd.assert().success().stdout(predicate::str::contains("the json template is valid"));
+
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg("validate").arg(r#"--body={"user": {"|x": "uuidds()", "|tags": "str(3) -> array(-1)"}}"#);
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("the field 'user.x' at column 1: the generator 'uuidds' is unknown, did you mean `uuid`?"))
+        .stderr(predicate::str::contains("the field 'user.tags' at column 17: the arguments of the generator 'array' are invalid"))
+        .stderr(predicate::str::contains("the json template has 2 error(s)"));
+    Ok(())
+}
+
+#[test]
+fn exit_code_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg(r#"--body={"|id": "int(1,2) -> arrayy(3)"}"#);
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("parser error [unknown-generator] (field 'id', generator 'arrayy'"));
+
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.args(["-b", r#"{"|id": "seq()"}"#, "--repeat", "many"]);
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("the value 'many' of the argument 'repeater' is invalid"));
+
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.args(["-b", r#"{"|id": "seq()"}"#, "--threads", "0"]);
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("the value '0' of the argument 'threads' is invalid"));
+
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.args(["-f", "jsons/missing_template.json"]);
+    cmd.assert()
+        .failure()
+        .code(3)
+        .stderr(predicate::str::contains("the json template can not be read from the file jsons/missing_template.json"));
+
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.args(["-b", r#"{"|id": "seq()"}"#, "--to-file", "jsons"]);
+    cmd.assert().failure().code(3).stderr(predicate::str::contains("should point to a file not to a folder"));
+
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.args(["-b", r#"{"|id": "seq()"}"#, "-r", "2", "--to-http", "http://127.0.0.1:1/items", "--http-timeout", "1"]);
+    cmd.assert()
+        .failure()
+        .code(4)
+        .stderr(predicate::str::contains("2 items have failed to be sent"));
+    Ok(())
+}
+
+#[test]
+fn generators_test() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("json-gen")?;
+    cmd.arg("generators");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("seq(start=0, step=1)\n    the sequentially increasing numbers\n    - start: the number to start from, 0 by default"))
+        .stdout(predicate::str::contains("example: int(1,10) -> array(2,5)"));
+
+    let output = Command::cargo_bin("json-gen")?.args(["generators", "--json"]).output()?;
+    assert!(output.status.success());
+    let generators: serde_json::Value = serde_json::from_slice(output.stdout.as_slice())?;
+    let generators = generators.as_array().unwrap();
+    assert_eq!(generators.len(), 22);
+    let fmt = generators.iter().find(|g| g["name"] == "fmt").unwrap();
+    assert_eq!(fmt["params"][0]["name"], "pattern");
+    assert_eq!(fmt["params"][0]["required"], true);
+    Ok(())
+}
+
+#[test]
+fn broken_pipe_test() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    for args in [vec!["--body", r#"{"|id": "seq()"}"#, "-r", "100000"], vec!["generators"]] {
+        let mut child = Command::cargo_bin("json-gen")?
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // the reader goes after the first line like `head -1`
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut line)?;
+        let output = child.wait_with_output()?;
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8(output.stderr)?, "");
+    }
+    Ok(())
+}